@@ -1,15 +1,43 @@
+mod assembler;
+mod cfg;
+mod disassembler;
 mod error;
+mod exception_clause;
+mod flow;
 mod helpers;
 mod instruction;
+mod label;
+mod locals;
 mod method;
 mod method_header;
+mod names;
 mod opcode;
+mod pattern;
+mod relaxation;
 mod section;
+mod signature;
+mod stack;
+mod token;
+mod transform;
+mod verify;
 
+pub use self::assembler::*;
+pub use self::cfg::*;
+pub use self::disassembler::*;
 pub use self::error::*;
+pub use self::exception_clause::*;
 pub use self::helpers::*;
 pub use self::instruction::*;
+pub use self::label::*;
+pub use self::locals::*;
 pub use self::method::*;
 pub use self::method_header::*;
+pub use self::names::*;
 pub use self::opcode::*;
+pub use self::pattern::*;
+pub use self::relaxation::*;
 pub use self::section::*;
+pub use self::signature::*;
+pub use self::token::*;
+pub use self::transform::*;
+pub use self::verify::*;
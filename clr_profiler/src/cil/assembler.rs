@@ -0,0 +1,461 @@
+use crate::cil::{
+    ClauseLabels, Error, FatMethodHeader, Instruction, Label, Method, MethodHeader, Opcode,
+    Operand, OperandParams, Section, SmallSectionClause, SmallSectionHeader, TinyMethodHeader,
+    Token,
+};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ops::Range;
+
+/// Parses ILAsm-like text into instructions, e.g.
+/// ```text
+///       ldarg.0
+///       brfalse.s skip
+///       call token(0x0A000012)
+/// skip: ret
+/// ```
+/// Branch targets refer to labels by name. Exception blocks need a method
+/// body, see `assemble_method`.
+pub fn assemble(text: &str) -> Result<Vec<Instruction>, Error> {
+    let assembler = Assembler::parse(text)?;
+    match assembler.clauses.first() {
+        Some(clause) => Err(Error::Syntax(
+            clause.line,
+            "exception blocks need a method body".to_string(),
+        )),
+        None => Ok(assembler.instructions),
+    }
+}
+
+/// Parses ILAsm-like text into a method body. On top of what `assemble`
+/// accepts, it supports `.maxstack`, `.locals [init] token(..)` and exception
+/// blocks in the format `disassemble` writes:
+/// ```text
+/// .try
+/// {
+///   leave.s end
+/// }
+/// catch token(0x01000001)
+/// {
+///   pop
+///   leave.s end
+/// }
+/// end: ret
+/// ```
+/// `finally`, `fault` and `filter { } { }` handlers are written the same way.
+pub fn assemble_method(text: &str) -> Result<Method, Error> {
+    let assembler = Assembler::parse(text)?;
+    let method_header = match (assembler.max_stack, assembler.local_var_sig_tok) {
+        (None, 0) => MethodHeader::Tiny(TinyMethodHeader { code_size: 0 }),
+        (max_stack, local_var_sig_tok) => MethodHeader::Fat(FatMethodHeader {
+            more_sects: false,
+            init_locals: assembler.init_locals,
+            max_stack: max_stack.unwrap_or(FatMethodHeader::TINY_MAX_STACK),
+            code_size: 0,
+            local_var_sig_tok,
+        }),
+    };
+    let mut method = Method {
+        method_header,
+        instructions: assembler.instructions,
+        sections: Vec::new(),
+    };
+    let mut clauses = Vec::new();
+    for clause in assembler.clauses.iter() {
+        let labels = ClauseLabels {
+            try_start: method.label_at(clause.try_.start),
+            try_last: method.label_at(clause.try_.end - 1),
+            handler_start: method.label_at(clause.handler.start),
+            handler_last: method.label_at(clause.handler.end - 1),
+            filter_start: clause.filter_start.map(|start| method.label_at(start)),
+        };
+        clauses.push(SmallSectionClause {
+            is_exception: true,
+            is_filter: clause.kind == BlockKind::Filter,
+            is_finally: clause.kind == BlockKind::Finally,
+            is_fault: clause.kind == BlockKind::Fault,
+            try_offset: 0,
+            try_length: 0,
+            handler_offset: 0,
+            handler_length: 0,
+            class_token_or_filter_offset: clause.class_token,
+            labels: Some(labels),
+        });
+    }
+    if !clauses.is_empty() {
+        let header = SmallSectionHeader {
+            is_eh_table: true,
+            more_sects: false,
+            data_size: 0,
+        };
+        let mut section = Section::SmallSection(header, clauses);
+        section.update_header(false);
+        method.sections.push(section);
+    }
+    Ok(method)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Try,
+    Catch,
+    Finally,
+    Fault,
+    Filter,
+    /// The handler block following a `filter` block.
+    FilterHandler,
+}
+struct OpenBlock {
+    kind: BlockKind,
+    start: usize,
+    class_token: u32,
+    filter_start: Option<usize>,
+    /// The protected region handlers at the enclosing level attach to.
+    outer_try: Option<Range<usize>>,
+}
+struct ParsedClause {
+    line: usize,
+    kind: BlockKind,
+    try_: Range<usize>,
+    handler: Range<usize>,
+    filter_start: Option<usize>,
+    class_token: u32,
+}
+#[derive(Default)]
+struct Assembler {
+    line: usize,
+    instructions: Vec<Instruction>,
+    clauses: Vec<ParsedClause>,
+    max_stack: Option<u16>,
+    local_var_sig_tok: u32,
+    init_locals: bool,
+    labels: HashMap<String, Label>,
+    /// Labels marking instructions, with the line they were defined on.
+    defined: HashMap<Label, usize>,
+    /// Labels of lines without an instruction, waiting for the next one.
+    pending_labels: Vec<String>,
+    open: Vec<OpenBlock>,
+    /// A block header waiting for its `{`, with its class token.
+    header: Option<(BlockKind, u32)>,
+    last_try: Option<Range<usize>>,
+    last_filter: Option<Range<usize>>,
+}
+impl Assembler {
+    fn parse(text: &str) -> Result<Self, Error> {
+        let mut assembler = Assembler::default();
+        for (number, line) in text.lines().enumerate() {
+            assembler.line = number + 1;
+            let line = match line.find("//") {
+                Some(comment) => &line[..comment],
+                None => line,
+            };
+            assembler
+                .parse_line(line.trim())
+                .map_err(|message| Error::Syntax(assembler.line, message))?;
+        }
+        let unexpected_end =
+            |message: &str| Err(Error::Syntax(assembler.line, message.to_string()));
+        if let Some(name) = assembler.pending_labels.first() {
+            return unexpected_end(&format!("label `{}` marks no instruction", name));
+        }
+        if !assembler.open.is_empty() || assembler.header.is_some() {
+            return unexpected_end("unclosed block");
+        }
+        for instruction in assembler.instructions.iter() {
+            for label in instruction.operand.targets() {
+                if !assembler.defined.contains_key(&label) {
+                    let name = assembler
+                        .labels
+                        .iter()
+                        .find(|(_, l)| **l == label)
+                        .unwrap()
+                        .0;
+                    return unexpected_end(&format!("undefined label `{}`", name));
+                }
+            }
+        }
+        Ok(assembler)
+    }
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        if line.is_empty() {
+            return Ok(());
+        }
+        let (word, rest) = split_word(line);
+        match word {
+            ".maxstack" => {
+                let max_stack = integer(rest).and_then(|value| u16::try_from(value).ok());
+                self.max_stack = Some(max_stack.ok_or("invalid .maxstack")?);
+                Ok(())
+            }
+            ".locals" => {
+                let (init, rest) = match split_word(rest) {
+                    ("init", rest) => (true, rest),
+                    _ => (false, rest),
+                };
+                self.init_locals = init;
+                self.local_var_sig_tok = token(rest)?;
+                Ok(())
+            }
+            ".try" => self.block_header(BlockKind::Try, 0, rest),
+            "catch" => {
+                let (class_token, rest) = match rest.strip_suffix('{') {
+                    Some(class_token) => (token(class_token.trim())?, "{"),
+                    None => (token(rest)?, ""),
+                };
+                self.block_header(BlockKind::Catch, class_token, rest)
+            }
+            "finally" => self.block_header(BlockKind::Finally, 0, rest),
+            "fault" => self.block_header(BlockKind::Fault, 0, rest),
+            "filter" => self.block_header(BlockKind::Filter, 0, rest),
+            "{" if rest.is_empty() => self.open_block(),
+            "}" if rest.is_empty() => self.close_block(),
+            _ => self.instruction(line),
+        }
+    }
+    fn block_header(
+        &mut self,
+        kind: BlockKind,
+        class_token: u32,
+        rest: &str,
+    ) -> Result<(), String> {
+        if self.header.is_some() {
+            return Err("expected `{`".to_string());
+        }
+        if kind != BlockKind::Try && self.last_try.is_none() {
+            return Err("handler without a preceding .try block".to_string());
+        }
+        self.last_filter = None;
+        self.header = Some((kind, class_token));
+        match rest {
+            "" => Ok(()),
+            "{" => self.open_block(),
+            _ => Err(format!("unexpected `{}`", rest)),
+        }
+    }
+    fn open_block(&mut self) -> Result<(), String> {
+        let filter = self.last_filter.take();
+        let (kind, class_token) = match (self.header.take(), &filter) {
+            (Some(header), _) => header,
+            (None, Some(_)) => (BlockKind::FilterHandler, 0),
+            (None, None) => return Err("block without a header".to_string()),
+        };
+        if !self.pending_labels.is_empty() {
+            return Err("labels must precede an instruction".to_string());
+        }
+        self.open.push(OpenBlock {
+            kind,
+            start: self.instructions.len(),
+            class_token,
+            filter_start: filter.map(|filter| filter.start),
+            outer_try: self.last_try.take(),
+        });
+        Ok(())
+    }
+    fn close_block(&mut self) -> Result<(), String> {
+        let block = self.open.pop().ok_or("unmatched `}`")?;
+        let region = block.start..self.instructions.len();
+        if region.is_empty() {
+            return Err("empty block".to_string());
+        }
+        self.last_try = block.outer_try;
+        self.last_filter = None;
+        let try_ = match (block.kind, &self.last_try) {
+            (BlockKind::Try, _) | (BlockKind::Filter, _) => {
+                if block.kind == BlockKind::Try {
+                    self.last_try = Some(region);
+                } else {
+                    self.last_filter = Some(region);
+                }
+                return Ok(());
+            }
+            (_, Some(try_)) => try_.clone(),
+            (_, None) => return Err("handler without a preceding .try block".to_string()),
+        };
+        let kind = match block.kind {
+            BlockKind::FilterHandler => BlockKind::Filter,
+            kind => kind,
+        };
+        self.clauses.push(ParsedClause {
+            line: self.line,
+            kind,
+            try_,
+            handler: region,
+            filter_start: block.filter_start,
+            class_token: block.class_token,
+        });
+        Ok(())
+    }
+    fn instruction(&mut self, line: &str) -> Result<(), String> {
+        if self.header.is_some() {
+            return Err("expected `{`".to_string());
+        }
+        self.last_try = None;
+        self.last_filter = None;
+        let (name, rest) = match line.find(':') {
+            Some(colon) if is_label(line[..colon].trim()) => {
+                (Some(line[..colon].trim()), line[colon + 1..].trim())
+            }
+            _ => (None, line),
+        };
+        if let Some(name) = name {
+            self.pending_labels.push(name.to_string());
+        }
+        if rest.is_empty() {
+            return Ok(());
+        }
+        let (mnemonic, operand) = split_word(rest);
+        let opcode =
+            Opcode::from_name(mnemonic).ok_or_else(|| format!("unknown opcode `{}`", mnemonic))?;
+        let operand = self.operand(opcode.operand_params, operand)?;
+        let mut instruction = Instruction::new(opcode, operand);
+        let names: Vec<String> = self.pending_labels.drain(..).collect();
+        for name in names {
+            let label = self.label(&name);
+            match instruction.label {
+                _ if self.defined.contains_key(&label) => {
+                    return Err(format!("label `{}` is defined twice", name));
+                }
+                Some(existing) => {
+                    // Several names for the same instruction
+                    self.rename(label, existing);
+                    self.labels.insert(name, existing);
+                }
+                None => instruction.label = Some(label),
+            }
+        }
+        if let Some(label) = instruction.label {
+            self.defined.insert(label, self.line);
+        }
+        self.instructions.push(instruction);
+        Ok(())
+    }
+    fn operand(&mut self, params: OperandParams, text: &str) -> Result<Operand, String> {
+        let invalid = || format!("invalid operand `{}`", text);
+        let ranged = |min: i128, max: i128| integer(text).filter(|v| (min..=max).contains(v));
+        let operand = match params {
+            OperandParams::InlineNone if text.is_empty() => Operand::InlineNone,
+            OperandParams::InlineNone => return Err(format!("unexpected operand `{}`", text)),
+            OperandParams::ShortInlineVar => {
+                Operand::ShortInlineVar(ranged(0, u8::MAX as i128).ok_or_else(invalid)? as u8)
+            }
+            OperandParams::InlineVar => {
+                Operand::InlineVar(ranged(0, u16::MAX as i128).ok_or_else(invalid)? as u16)
+            }
+            OperandParams::ShortInlineI => Operand::ShortInlineI(
+                ranged(i8::MIN as i128, u8::MAX as i128).ok_or_else(invalid)? as u8,
+            ),
+            OperandParams::InlineI => Operand::InlineI(
+                ranged(i32::MIN as i128, u32::MAX as i128).ok_or_else(invalid)? as u32 as i32,
+            ),
+            OperandParams::InlineI8 => Operand::InlineI8(
+                ranged(i64::MIN as i128, u64::MAX as i128).ok_or_else(invalid)? as u64 as i64,
+            ),
+            OperandParams::ShortInlineR => {
+                Operand::ShortInlineR(text.parse().map_err(|_| invalid())?)
+            }
+            OperandParams::InlineR => Operand::InlineR(text.parse().map_err(|_| invalid())?),
+            OperandParams::InlineMethod => Operand::InlineMethod(typed_token(&params, text)?),
+            OperandParams::InlineSig => Operand::InlineSig(token(text)?),
+            OperandParams::InlineType => Operand::InlineType(typed_token(&params, text)?),
+            OperandParams::InlineString => Operand::InlineString(typed_token(&params, text)?),
+            OperandParams::InlineField => Operand::InlineField(typed_token(&params, text)?),
+            OperandParams::InlineTok => Operand::InlineTok(typed_token(&params, text)?),
+            OperandParams::ShortInlineBrTarget | OperandParams::InlineBrTarget => {
+                if !is_label(text) {
+                    return Err(invalid());
+                }
+                Operand::BrTarget(self.label(text))
+            }
+            OperandParams::InlineSwitch => {
+                let names = text
+                    .strip_prefix('(')
+                    .and_then(|text| text.strip_suffix(')'))
+                    .ok_or_else(invalid)?;
+                let mut labels = Vec::new();
+                for name in names
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                {
+                    if !is_label(name) {
+                        return Err(invalid());
+                    }
+                    labels.push(self.label(name));
+                }
+                Operand::SwitchTargets(labels)
+            }
+        };
+        Ok(operand)
+    }
+    /// Label for a name, allocated on first use so branches can refer to
+    /// labels defined further down.
+    fn label(&mut self, name: &str) -> Label {
+        let next = Label(self.labels.len() as u32);
+        *self.labels.entry(name.to_string()).or_insert(next)
+    }
+    fn rename(&mut self, from: Label, to: Label) {
+        for instruction in self.instructions.iter_mut() {
+            match &mut instruction.operand {
+                Operand::BrTarget(label) if *label == from => *label = to,
+                Operand::SwitchTargets(labels) => labels
+                    .iter_mut()
+                    .filter(|label| **label == from)
+                    .for_each(|label| *label = to),
+                _ => (),
+            }
+        }
+        for label in self.labels.values_mut().filter(|label| **label == from) {
+            *label = to;
+        }
+    }
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    match text.find(char::is_whitespace) {
+        Some(end) => (&text[..end], text[end..].trim()),
+        None => (text, ""),
+    }
+}
+
+fn is_label(text: &str) -> bool {
+    !text.is_empty()
+        && !text.starts_with(|c: char| c.is_ascii_digit())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$' || c == '.')
+}
+
+/// Decimal or `0x` prefixed hexadecimal integer, optionally negative.
+fn integer(text: &str) -> Option<i128> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<u64>().ok()?,
+    } as i128;
+    Some(if negative { -value } else { value })
+}
+
+/// Metadata token written as `token(0x0A000012)` or just `0x0A000012`.
+fn token(text: &str) -> Result<u32, String> {
+    let value = text
+        .strip_prefix("token(")
+        .and_then(|text| text.strip_suffix(')'))
+        .unwrap_or(text);
+    match value.starts_with("0x") || value.starts_with("0X") {
+        true => integer(value).and_then(|value| u32::try_from(value).ok()),
+        false => None,
+    }
+    .ok_or_else(|| format!("invalid token `{}`", text))
+}
+fn typed_token(params: &OperandParams, text: &str) -> Result<Token, String> {
+    Token::from_raw(token(text)?)
+        .ok()
+        .filter(|token| token.fits(params))
+        .ok_or_else(|| format!("invalid token `{}`", text))
+}
@@ -0,0 +1,247 @@
+use crate::cil::{
+    flow::{self, ClauseRegions, Layout},
+    Error, Method,
+};
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Fallthrough, branch, switch or `leave`.
+    Normal,
+    /// From a block inside a protected region to its handler or filter.
+    Exceptional,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    /// Index of the block at the other end of the edge.
+    pub block: usize,
+    pub kind: EdgeKind,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    /// Indices into `Method::instructions`.
+    pub instructions: Range<usize>,
+    pub successors: Vec<Edge>,
+    pub predecessors: Vec<Edge>,
+}
+/// A natural loop: every block of `blocks` reaches a back edge to `header`
+/// without leaving the loop, and `header` dominates all of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loop {
+    pub header: usize,
+    /// Blocks with a back edge to the header.
+    pub latches: Vec<usize>,
+    /// All blocks of the loop, including the header, in ascending order.
+    pub blocks: Vec<usize>,
+}
+/// Basic blocks of a method body. Block 0 is the entry, handler and filter
+/// entries are only reached through exceptional edges.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+}
+impl ControlFlowGraph {
+    pub fn new(method: &Method) -> Result<Self, Error> {
+        let instructions = &method.instructions;
+        let layout = Layout::new(instructions)?;
+        let regions = flow::clauses(method)
+            .iter()
+            .map(|clause| ClauseRegions::new(clause, &layout))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::InvalidExceptionClause)?;
+
+        let mut leaders = BTreeSet::new();
+        leaders.insert(0);
+        for region in regions.iter().flat_map(|r| r.all()) {
+            leaders.insert(region.start);
+            leaders.insert(region.end);
+        }
+        let mut successors = Vec::with_capacity(instructions.len());
+        for index in 0..instructions.len() {
+            let instruction_successors = layout.successors(instructions, index)?;
+            if instruction_successors != [index + 1] {
+                leaders.insert(index + 1);
+                leaders.extend(instruction_successors.iter().copied());
+            }
+            successors.push(instruction_successors);
+        }
+        leaders.retain(|leader| *leader < instructions.len());
+
+        let starts: Vec<usize> = leaders.into_iter().collect();
+        let mut blocks: Vec<BasicBlock> = starts
+            .iter()
+            .enumerate()
+            .map(|(block, start)| {
+                let end = starts.get(block + 1).copied().unwrap_or(instructions.len());
+                BasicBlock {
+                    instructions: *start..end,
+                    successors: Vec::new(),
+                    predecessors: Vec::new(),
+                }
+            })
+            .collect();
+        let block_of = |index: usize| starts.binary_search(&index).ok();
+
+        let mut edges = Vec::new();
+        for (block, basic_block) in blocks.iter().enumerate() {
+            let last = basic_block.instructions.end - 1;
+            for target in successors[last].iter().filter_map(|s| block_of(*s)) {
+                edges.push((block, target, EdgeKind::Normal));
+            }
+            for clause_regions in regions.iter() {
+                if !clause_regions
+                    .try_
+                    .contains(&basic_block.instructions.start)
+                {
+                    continue;
+                }
+                let entry = clause_regions
+                    .filter
+                    .as_ref()
+                    .unwrap_or(&clause_regions.handler)
+                    .start;
+                edges.extend(block_of(entry).map(|target| (block, target, EdgeKind::Exceptional)));
+            }
+        }
+        for (from, to, kind) in edges {
+            let successor = Edge { block: to, kind };
+            if !blocks[from].successors.contains(&successor) {
+                blocks[from].successors.push(successor);
+                blocks[to].predecessors.push(Edge { block: from, kind });
+            }
+        }
+        Ok(ControlFlowGraph { blocks })
+    }
+    /// Index of the block containing the instruction at `index`.
+    pub fn block_of(&self, index: usize) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|block| block.instructions.contains(&index))
+    }
+    /// Immediate dominator of each block, over both normal and exceptional
+    /// edges. The entry block and unreachable blocks have none.
+    pub fn dominators(&self) -> Vec<Option<usize>> {
+        let order = self.reverse_postorder();
+        let mut rank = vec![usize::MAX; self.blocks.len()];
+        for (position, block) in order.iter().enumerate() {
+            rank[*block] = position;
+        }
+        let mut idoms: Vec<Option<usize>> = vec![None; self.blocks.len()];
+        if self.blocks.is_empty() {
+            return idoms;
+        }
+        idoms[0] = Some(0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in order.iter().skip(1) {
+                let mut processed = self.blocks[*block]
+                    .predecessors
+                    .iter()
+                    .map(|edge| edge.block)
+                    .filter(|predecessor| idoms[*predecessor].is_some());
+                let first = match processed.next() {
+                    Some(first) => first,
+                    None => continue,
+                };
+                let idom = processed.fold(first, |a, b| Self::intersect(&idoms, &rank, a, b));
+                if idoms[*block] != Some(idom) {
+                    idoms[*block] = Some(idom);
+                    changed = true;
+                }
+            }
+        }
+        idoms[0] = None;
+        idoms
+    }
+    /// Whether every path from the entry to `block` goes through `dominator`.
+    pub fn dominates(&self, dominator: usize, block: usize) -> bool {
+        Self::dominated(&self.dominators(), dominator, block)
+    }
+    /// Natural loops found through normal back edges, one per header.
+    pub fn loops(&self) -> Vec<Loop> {
+        let idoms = self.dominators();
+        let mut loops: Vec<Loop> = Vec::new();
+        for (latch, block) in self.blocks.iter().enumerate() {
+            let back_edges = block.successors.iter().filter(|edge| {
+                edge.kind == EdgeKind::Normal && Self::dominated(&idoms, edge.block, latch)
+            });
+            for edge in back_edges {
+                let header = edge.block;
+                let mut body = BTreeSet::new();
+                body.insert(header);
+                let mut pending = vec![latch];
+                while let Some(block) = pending.pop() {
+                    if body.insert(block) {
+                        pending.extend(
+                            self.blocks[block]
+                                .predecessors
+                                .iter()
+                                .filter(|edge| edge.kind == EdgeKind::Normal)
+                                .map(|edge| edge.block),
+                        );
+                    }
+                }
+                match loops.iter_mut().find(|l| l.header == header) {
+                    Some(existing) => {
+                        existing.latches.push(latch);
+                        body.extend(existing.blocks.iter().copied());
+                        existing.blocks = body.into_iter().collect();
+                    }
+                    None => loops.push(Loop {
+                        header,
+                        latches: vec![latch],
+                        blocks: body.into_iter().collect(),
+                    }),
+                }
+            }
+        }
+        loops
+    }
+    fn dominated(idoms: &[Option<usize>], dominator: usize, block: usize) -> bool {
+        let mut current = Some(block);
+        while let Some(block) = current {
+            if block == dominator {
+                return true;
+            }
+            current = idoms[block];
+        }
+        false
+    }
+    fn reverse_postorder(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.blocks.len()];
+        let mut postorder = Vec::with_capacity(self.blocks.len());
+        let mut stack = Vec::new();
+        if !self.blocks.is_empty() {
+            visited[0] = true;
+            stack.push((0, 0));
+        }
+        while let Some((block, next)) = stack.pop() {
+            match self.blocks[block].successors.get(next) {
+                Some(edge) => {
+                    stack.push((block, next + 1));
+                    if !visited[edge.block] {
+                        visited[edge.block] = true;
+                        stack.push((edge.block, 0));
+                    }
+                }
+                None => postorder.push(block),
+            }
+        }
+        postorder.reverse();
+        postorder
+    }
+    fn intersect(idoms: &[Option<usize>], rank: &[usize], a: usize, b: usize) -> usize {
+        let (mut a, mut b) = (a, b);
+        while a != b {
+            while rank[a] > rank[b] {
+                a = idoms[a].unwrap();
+            }
+            while rank[b] > rank[a] {
+                b = idoms[b].unwrap();
+            }
+        }
+        a
+    }
+}
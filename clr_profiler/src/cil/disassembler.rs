@@ -0,0 +1,307 @@
+use crate::{
+    cil::{
+        flow::{self, ClauseRegions, Layout},
+        signature_type_name, type_name, FatSectionClause, Instruction, Label, Method, MethodHeader,
+        Operand, Signature, Token, TokenTable, Type,
+    },
+    MetadataImportTrait,
+};
+use std::fmt::{self, Display, Write};
+
+/// Renders a method body as ILDasm-style text. Tokens `name_of` cannot
+/// resolve are rendered as `token(0x0A000001)`.
+pub fn disassemble<F>(method: &Method, mut name_of: F) -> String
+where
+    F: FnMut(u32) -> Option<String>,
+{
+    let instructions = &method.instructions;
+    let mut text = String::new();
+    match &method.method_header {
+        MethodHeader::Fat(header) => {
+            writeln!(text, ".maxstack {}", header.max_stack).unwrap();
+            if header.local_var_sig_tok != 0 {
+                let init = if header.init_locals { "init " } else { "" };
+                writeln!(
+                    text,
+                    ".locals {}token(0x{:08X})",
+                    init, header.local_var_sig_tok
+                )
+                .unwrap();
+            }
+        }
+        MethodHeader::Tiny(_) => writeln!(text, ".maxstack 8").unwrap(),
+    }
+    let layout = match Layout::new(instructions) {
+        Ok(layout) => layout,
+        // Without a consistent layout there are no offsets to show
+        Err(_) => {
+            for instruction in instructions.iter() {
+                writeln!(text, "{}", instruction).unwrap();
+            }
+            return text;
+        }
+    };
+
+    let mut blocks = blocks(method, &layout, &mut name_of);
+    blocks.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+    let mut open: Vec<usize> = Vec::new();
+    let mut pending = blocks.iter().peekable();
+    for (index, instruction) in instructions.iter().enumerate() {
+        while matches!(open.last(), Some(end) if *end <= index) {
+            open.pop();
+            writeln!(text, "{}}}", indent(open.len())).unwrap();
+        }
+        while let Some(block) = pending.next_if(|block| block.start == index) {
+            if let Some(header) = &block.header {
+                writeln!(text, "{}{}", indent(open.len()), header).unwrap();
+            }
+            writeln!(text, "{}{{", indent(open.len())).unwrap();
+            open.push(block.end);
+        }
+        write!(
+            text,
+            "{}IL_{:04x}: {}",
+            indent(open.len()),
+            layout.offsets[index],
+            instruction.opcode.name
+        )
+        .unwrap();
+        let operand = operand(instructions, index, &layout, &mut name_of);
+        if !operand.is_empty() {
+            write!(text, " {}", operand).unwrap();
+        }
+        text.push('\n');
+    }
+    while open.pop().is_some() {
+        writeln!(text, "{}}}", indent(open.len())).unwrap();
+    }
+    text
+}
+
+/// Name of a type, method, field or user string token, e.g.
+/// `System.Console::WriteLine`. Types are named by `type_name`, nested ones
+/// after their enclosing type. Type specifications are named after the type
+/// in their blob, and method instantiations after their method and type
+/// arguments, e.g. `System.Array::Empty<int32>`.
+pub fn token_name<T: MetadataImportTrait>(metadata: &T, token: u32) -> Option<String> {
+    let member = |parent: u32, name: String| match token_name(metadata, parent) {
+        Some(parent) => format!("{}::{}", parent, name),
+        None => name,
+    };
+    match Token::from_raw(token).ok()?.table() {
+        TokenTable::TypeRef | TokenTable::TypeDef => type_name(metadata, token).ok(),
+        TokenTable::FieldDef => {
+            let props = metadata.get_field_props(token).ok()?;
+            Some(member(props.class_token, props.name))
+        }
+        TokenTable::MethodDef => {
+            let props = metadata.get_method_props(token).ok()?;
+            Some(member(props.class_token, props.name))
+        }
+        TokenTable::MemberRef => {
+            let props = metadata.get_member_ref_props(token).ok()?;
+            Some(member(props.parent_token, props.name))
+        }
+        TokenTable::TypeSpec => {
+            let blob = metadata.get_type_spec_from_token(token).ok()?;
+            signature_type_name(metadata, &Type::from_bytes(&blob).ok()?).ok()
+        }
+        TokenTable::MethodSpec => {
+            let props = metadata.get_method_spec_props(token).ok()?;
+            let method = token_name(metadata, props.parent)?;
+            let args = match Signature::from_bytes(&props.sig).ok()? {
+                Signature::MethodSpec(args) => args
+                    .iter()
+                    .map(|arg| signature_type_name(metadata, arg))
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok()?,
+                _ => return None,
+            };
+            Some(format!("{}<{}>", method, args.join(", ")))
+        }
+        TokenTable::UserString => metadata.get_user_string(token).ok(),
+        _ => None,
+    }
+}
+
+impl Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IL_{:04x}", self.0)
+    }
+}
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(label) = self.label {
+            write!(f, "{}: ", label)?;
+        }
+        write!(f, "{}", self.opcode.name)?;
+        let operand = raw(&self.operand);
+        match operand.is_empty() {
+            true => Ok(()),
+            false => write!(f, " {}", operand),
+        }
+    }
+}
+impl Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&disassemble(self, |_| None))
+    }
+}
+
+/// A `{ }` block opened before the instruction at `start` and closed before
+/// the one at `end`, with an optional line preceding the brace.
+struct Block {
+    start: usize,
+    end: usize,
+    header: Option<String>,
+}
+
+fn blocks<F>(method: &Method, layout: &Layout, name_of: &mut F) -> Vec<Block>
+where
+    F: FnMut(u32) -> Option<String>,
+{
+    let clauses = flow::clauses(method);
+    let mut blocks: Vec<Block> = Vec::new();
+    for clause in clauses.iter() {
+        let regions = match ClauseRegions::new(clause, layout) {
+            Some(regions) => regions,
+            None => continue,
+        };
+        // Handlers sharing a protected region share its `.try` block
+        if !blocks
+            .iter()
+            .any(|b| b.start == regions.try_.start && b.end == regions.try_.end)
+        {
+            blocks.push(Block {
+                start: regions.try_.start,
+                end: regions.try_.end,
+                header: Some(".try".to_string()),
+            });
+        }
+        let header = handler_header(clause, name_of);
+        match regions.filter {
+            Some(filter) => {
+                blocks.push(Block {
+                    start: filter.start,
+                    end: filter.end,
+                    header: Some(header),
+                });
+                blocks.push(Block {
+                    start: regions.handler.start,
+                    end: regions.handler.end,
+                    header: None,
+                });
+            }
+            None => blocks.push(Block {
+                start: regions.handler.start,
+                end: regions.handler.end,
+                header: Some(header),
+            }),
+        }
+    }
+    blocks
+}
+
+fn handler_header<F>(clause: &FatSectionClause, name_of: &mut F) -> String
+where
+    F: FnMut(u32) -> Option<String>,
+{
+    if clause.is_finally {
+        "finally".to_string()
+    } else if clause.is_fault {
+        "fault".to_string()
+    } else if clause.is_filter {
+        "filter".to_string()
+    } else {
+        format!(
+            "catch {}",
+            token(clause.class_token_or_filter_offset, name_of)
+        )
+    }
+}
+
+fn operand<F>(
+    instructions: &[Instruction],
+    index: usize,
+    layout: &Layout,
+    name_of: &mut F,
+) -> String
+where
+    F: FnMut(u32) -> Option<String>,
+{
+    let operand = &instructions[index].operand;
+    let target = |index: &usize| format!("IL_{:04x}", layout.offsets[*index]);
+    match operand {
+        Operand::ShortInlineBrTarget(_) | Operand::InlineBrTarget(_) | Operand::BrTarget(_) => {
+            match layout.targets(instructions, index) {
+                Ok(targets) => targets.iter().map(target).collect(),
+                Err(_) => raw(operand),
+            }
+        }
+        Operand::InlineSwitch(..) | Operand::SwitchTargets(_) => {
+            match layout.targets(instructions, index) {
+                Ok(targets) => {
+                    let targets: Vec<_> = targets.iter().map(target).collect();
+                    format!("({})", targets.join(", "))
+                }
+                Err(_) => raw(operand),
+            }
+        }
+        operand => scalar(operand, name_of),
+    }
+}
+
+/// Operand text without offsets or metadata to resolve it against.
+fn raw(operand: &Operand) -> String {
+    match operand {
+        Operand::ShortInlineBrTarget(delta) => format!("{:+}", delta),
+        Operand::InlineBrTarget(delta) => format!("{:+}", delta),
+        Operand::InlineSwitch(_, deltas) => {
+            let deltas: Vec<_> = deltas.iter().map(|delta| format!("{:+}", delta)).collect();
+            format!("({})", deltas.join(", "))
+        }
+        Operand::BrTarget(label) => label.to_string(),
+        Operand::SwitchTargets(labels) => {
+            let labels: Vec<_> = labels.iter().map(Label::to_string).collect();
+            format!("({})", labels.join(", "))
+        }
+        operand => scalar(operand, &mut |_| None),
+    }
+}
+
+fn scalar<F>(operand: &Operand, name_of: &mut F) -> String
+where
+    F: FnMut(u32) -> Option<String>,
+{
+    match operand {
+        Operand::InlineNone => String::new(),
+        Operand::ShortInlineVar(value) => value.to_string(),
+        Operand::InlineVar(value) => value.to_string(),
+        Operand::ShortInlineI(value) => (*value as i8).to_string(),
+        Operand::InlineI(value) => value.to_string(),
+        Operand::InlineI8(value) => value.to_string(),
+        Operand::ShortInlineR(value) => format!("{:?}", value),
+        Operand::InlineR(value) => format!("{:?}", value),
+        Operand::InlineString(value) => match name_of(value.raw()) {
+            Some(string) => format!("{:?}", string),
+            None => format!("token(0x{:08X})", value.raw()),
+        },
+        Operand::InlineSig(value) => token(*value, name_of),
+        Operand::InlineMethod(value)
+        | Operand::InlineType(value)
+        | Operand::InlineField(value)
+        | Operand::InlineTok(value) => token(value.raw(), name_of),
+        _ => String::new(),
+    }
+}
+
+fn token<F>(token: u32, name_of: &mut F) -> String
+where
+    F: FnMut(u32) -> Option<String>,
+{
+    name_of(token).unwrap_or_else(|| format!("token(0x{:08X})", token))
+}
+
+fn indent(depth: usize) -> String {
+    "  ".repeat(depth)
+}
@@ -1,4 +1,6 @@
-#[derive(Debug)]
+use crate::{cil::Label, ffi::HRESULT};
+
+#[derive(Debug, PartialEq)]
 #[non_exhaustive]
 pub enum Error {
     InvalidMethodHeader,
@@ -6,4 +8,24 @@ pub enum Error {
     InvalidCil,
     InvalidCilOpcode,
     PreludeTooBig,
+    /// Instruction index or range past the end of the method.
+    OutOfRange,
+    InvalidBranchTarget,
+    InvalidExceptionClause,
+    UndefinedLabel(Label),
+    DuplicateLabel(Label),
+    BranchOutOfRange(Label),
+    InvalidSignature,
+    /// Token of an unknown table, or of a table the operand can't refer to.
+    InvalidToken(u32),
+    UnresolvedSignature(u32),
+    StackUnderflow(usize),
+    /// Line number, starting at 1, and description of malformed IL text.
+    Syntax(usize, String),
+    /// Offset into the method body and description of what failed to parse there.
+    Malformed(usize, String),
+    /// Offset of the first byte that changed when serializing a reparsed body.
+    RoundTrip(usize),
+    /// A metadata API call failed with this HRESULT.
+    Metadata(HRESULT),
 }
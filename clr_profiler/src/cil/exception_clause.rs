@@ -0,0 +1,171 @@
+use crate::cil::{
+    flow::{self, ClauseRegions, Layout},
+    ClauseLabels, Error, FatSectionClause, Method, OperandParams, Section, SmallSectionClause,
+    SmallSectionHeader, Token,
+};
+use std::ops::Range;
+
+/// What an exception handling clause does with exceptions leaving its try block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClauseKind {
+    /// Handles exceptions of the type with this token, a type definition,
+    /// reference or specification.
+    Catch(Token),
+    /// Handles exceptions accepted by the filter starting at this instruction
+    /// index. The filter runs up to the start of the handler.
+    Filter(usize),
+    Finally,
+    Fault,
+}
+
+/// Exception handling clause with its regions given as ranges of instruction
+/// indices, instead of the byte offsets `FatSectionClause` and
+/// `SmallSectionClause` are encoded with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExceptionClause {
+    pub kind: ClauseKind,
+    pub try_range: Range<usize>,
+    pub handler_range: Range<usize>,
+}
+
+impl Method {
+    /// Clauses of every section, in order.
+    pub fn exception_clauses(&self) -> Result<Vec<ExceptionClause>, Error> {
+        let layout = Layout::new(&self.instructions)?;
+        flow::clauses(self)
+            .iter()
+            .map(|clause| {
+                let regions =
+                    ClauseRegions::new(clause, &layout).ok_or(Error::InvalidExceptionClause)?;
+                let kind = match &regions.filter {
+                    Some(filter) => ClauseKind::Filter(filter.start),
+                    None if clause.is_finally => ClauseKind::Finally,
+                    None if clause.is_fault => ClauseKind::Fault,
+                    None => {
+                        ClauseKind::Catch(Token::from_raw(clause.class_token_or_filter_offset)?)
+                    }
+                };
+                Ok(ExceptionClause {
+                    kind,
+                    try_range: regions.try_,
+                    handler_range: regions.handler,
+                })
+            })
+            .collect()
+    }
+    /// Adds an exception handling clause. Its regions are labelled, so they
+    /// follow the instructions they cover through later insertions and
+    /// removals, and the encoding is chosen when serializing: small unless an
+    /// offset or length doesn't fit.
+    ///
+    /// Clauses must come before the clauses enclosing them, so the clause goes
+    /// after the last clause it encloses and before the first clause enclosing
+    /// it, whichever sections they are in. Without an enclosing clause it's
+    /// appended to the last section. Clauses protecting the same instructions
+    /// are siblings rather than nested, so a new sibling is tried after the
+    /// existing ones. If no position fits, `Error::InvalidExceptionClause`.
+    pub fn add_exception_clause(&mut self, clause: ExceptionClause) -> Result<(), Error> {
+        let count = self.instructions.len();
+        let valid = |range: &Range<usize>| range.start < range.end && range.end <= count;
+        let filter_start = match clause.kind {
+            ClauseKind::Filter(start) if start < clause.handler_range.start => Some(start),
+            ClauseKind::Filter(_) => return Err(Error::InvalidExceptionClause),
+            ClauseKind::Catch(token) if !token.fits(&OperandParams::InlineType) => {
+                return Err(Error::InvalidExceptionClause)
+            }
+            _ => None,
+        };
+        if !valid(&clause.try_range) || !valid(&clause.handler_range) {
+            return Err(Error::InvalidExceptionClause);
+        }
+
+        let inside = |inner: &Range<usize>, outer: &Range<usize>| {
+            outer.start <= inner.start && inner.end <= outer.end
+        };
+        let clauses = self.exception_clauses()?;
+        let enclosing = clauses.iter().position(|other| {
+            other.try_range != clause.try_range
+                && (inside(&clause.try_range, &other.try_range)
+                    || inside(&clause.try_range, &other.handler_range))
+        });
+        let last_enclosed = clauses.iter().rposition(|other| {
+            other.try_range != clause.try_range
+                && (inside(&other.try_range, &clause.try_range)
+                    || inside(&other.try_range, &clause.handler_range))
+        });
+        if let (Some(enclosing), Some(last_enclosed)) = (enclosing, last_enclosed) {
+            if last_enclosed >= enclosing {
+                return Err(Error::InvalidExceptionClause);
+            }
+        }
+        let counts: Vec<usize> = self
+            .sections
+            .iter()
+            .map(|section| match section {
+                Section::FatSection(_, clauses) => clauses.len(),
+                Section::SmallSection(_, clauses) => clauses.len(),
+            })
+            .collect();
+        // Section the clause goes into, and its index among the clauses there
+        let (section_index, position) = match enclosing {
+            Some(mut position) => {
+                let mut section_index = 0;
+                while position >= counts[section_index] {
+                    position -= counts[section_index];
+                    section_index += 1;
+                }
+                (section_index, position)
+            }
+            None => (
+                counts.len().saturating_sub(1),
+                counts.last().copied().unwrap_or(0),
+            ),
+        };
+
+        let labels = ClauseLabels {
+            try_start: self.label_at(clause.try_range.start),
+            try_last: self.label_at(clause.try_range.end - 1),
+            handler_start: self.label_at(clause.handler_range.start),
+            handler_last: self.label_at(clause.handler_range.end - 1),
+            filter_start: filter_start.map(|start| self.label_at(start)),
+        };
+        let small = SmallSectionClause {
+            is_exception: true,
+            is_filter: filter_start.is_some(),
+            is_finally: clause.kind == ClauseKind::Finally,
+            is_fault: clause.kind == ClauseKind::Fault,
+            try_offset: 0,
+            try_length: 0,
+            handler_offset: 0,
+            handler_length: 0,
+            class_token_or_filter_offset: match clause.kind {
+                ClauseKind::Catch(token) => token.raw(),
+                _ => 0,
+            },
+            labels: Some(labels),
+        };
+        let more_sects = section_index + 1 < self.sections.len();
+        match self.sections.get_mut(section_index) {
+            Some(section) => {
+                match section {
+                    Section::FatSection(_, clauses) => {
+                        clauses.insert(position, FatSectionClause::from(&small))
+                    }
+                    Section::SmallSection(_, clauses) => clauses.insert(position, small),
+                }
+                section.update_header(more_sects);
+            }
+            None => {
+                let header = SmallSectionHeader {
+                    is_eh_table: true,
+                    more_sects: false,
+                    data_size: 0,
+                };
+                let mut section = Section::SmallSection(header, vec![small]);
+                section.update_header(false);
+                self.sections.push(section);
+            }
+        }
+        Ok(())
+    }
+}
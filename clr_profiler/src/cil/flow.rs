@@ -0,0 +1,184 @@
+use crate::cil::{
+    ControlFlow, Error, FatSectionClause, Instruction, Label, Method, Operand, Section, JMP, LEAVE,
+    LEAVE_S,
+};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ops::Range;
+
+/// Byte offsets and label positions of a sequence of instructions.
+pub(crate) struct Layout {
+    pub offsets: Vec<usize>,
+    pub code_size: usize,
+    positions: HashMap<Label, usize>,
+}
+impl Layout {
+    pub fn new(instructions: &[Instruction]) -> Result<Self, Error> {
+        let mut offsets = Vec::with_capacity(instructions.len());
+        let mut positions = HashMap::new();
+        let mut code_size = 0;
+        for (index, instruction) in instructions.iter().enumerate() {
+            offsets.push(code_size);
+            code_size += instruction.length();
+            if let Some(label) = instruction.label {
+                if positions.insert(label, index).is_some() {
+                    return Err(Error::DuplicateLabel(label));
+                }
+            }
+        }
+        Ok(Layout {
+            offsets,
+            code_size,
+            positions,
+        })
+    }
+    /// Index of the instruction marked with `label`.
+    pub fn position(&self, label: Label) -> Result<usize, Error> {
+        self.positions
+            .get(&label)
+            .copied()
+            .ok_or(Error::UndefinedLabel(label))
+    }
+    /// Index of the instruction starting at `offset`. The end of the code maps
+    /// to the number of instructions.
+    pub fn index_at(&self, offset: usize) -> Option<usize> {
+        match offset == self.code_size {
+            true => Some(self.offsets.len()),
+            false => self.offsets.binary_search(&offset).ok(),
+        }
+    }
+    /// Indices of the branch targets of the instruction at `index`.
+    pub fn targets(&self, instructions: &[Instruction], index: usize) -> Result<Vec<usize>, Error> {
+        let next = (self.offsets[index] + instructions[index].length()) as i64;
+        let absolute = |delta: i64| {
+            usize::try_from(next + delta)
+                .ok()
+                .and_then(|offset| self.index_at(offset))
+                .filter(|index| *index < self.offsets.len())
+                .ok_or(Error::InvalidBranchTarget)
+        };
+        match &instructions[index].operand {
+            Operand::BrTarget(_) | Operand::SwitchTargets(_) => instructions[index]
+                .operand
+                .targets()
+                .into_iter()
+                .map(|label| self.position(label))
+                .collect(),
+            Operand::ShortInlineBrTarget(delta) => Ok(vec![absolute(*delta as i64)?]),
+            Operand::InlineBrTarget(delta) => Ok(vec![absolute(*delta as i64)?]),
+            Operand::InlineSwitch(_, deltas) => {
+                deltas.iter().map(|delta| absolute(*delta as i64)).collect()
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+    /// Indices of the instructions control can reach right after the one at
+    /// `index`, ignoring exceptions. An index equal to the number of
+    /// instructions means control falls off the end of the body.
+    pub fn successors(
+        &self,
+        instructions: &[Instruction],
+        index: usize,
+    ) -> Result<Vec<usize>, Error> {
+        let instruction = &instructions[index];
+        let successors = match instruction.opcode.control_flow {
+            ControlFlow::Return | ControlFlow::Throw => Vec::new(),
+            _ if instruction.opcode == JMP => Vec::new(),
+            ControlFlow::Branch => self.targets(instructions, index)?,
+            ControlFlow::CondBranch => {
+                let mut successors = vec![index + 1];
+                successors.extend(self.targets(instructions, index)?);
+                successors
+            }
+            _ => vec![index + 1],
+        };
+        Ok(successors)
+    }
+}
+
+/// Instruction index ranges covered by an exception handling clause.
+pub(crate) struct ClauseRegions {
+    pub try_: Range<usize>,
+    pub handler: Range<usize>,
+    pub filter: Option<Range<usize>>,
+    pub is_finally_or_fault: bool,
+}
+impl ClauseRegions {
+    pub fn new(clause: &FatSectionClause, layout: &Layout) -> Option<Self> {
+        let (try_, handler, filter_start) = match &clause.labels {
+            Some(labels) => {
+                let region = |start, last| -> Option<Range<usize>> {
+                    Some(layout.position(start).ok()?..layout.position(last).ok()? + 1)
+                };
+                let filter_start = match labels.filter_start {
+                    Some(label) => Some(layout.position(label).ok()?),
+                    None => None,
+                };
+                (
+                    region(labels.try_start, labels.try_last)?,
+                    region(labels.handler_start, labels.handler_last)?,
+                    filter_start,
+                )
+            }
+            None => {
+                let region = |offset: u32, length: u32| -> Option<Range<usize>> {
+                    let start = layout.index_at(offset as usize)?;
+                    let end = layout.index_at(offset as usize + length as usize)?;
+                    Some(start..end)
+                };
+                let filter_start = match clause.is_filter {
+                    true => Some(layout.index_at(clause.class_token_or_filter_offset as usize)?),
+                    false => None,
+                };
+                (
+                    region(clause.try_offset, clause.try_length)?,
+                    region(clause.handler_offset, clause.handler_length)?,
+                    filter_start,
+                )
+            }
+        };
+        let filter = filter_start.map(|start| start..handler.start);
+        let regions = ClauseRegions {
+            try_,
+            handler,
+            filter,
+            is_finally_or_fault: clause.is_finally || clause.is_fault,
+        };
+        let disjoint = |a: &Range<usize>, b: &Range<usize>| a.end <= b.start || b.end <= a.start;
+        let valid = regions.all().iter().all(|r| r.start < r.end)
+            && regions
+                .all()
+                .iter()
+                .all(|r| disjoint(&regions.try_, r) || *r == regions.try_)
+            && disjoint(&regions.handler, regions.filter.as_ref().unwrap_or(&(0..0)));
+        match valid {
+            true => Some(regions),
+            false => None,
+        }
+    }
+    /// The protected region, the handler and the filter, if any.
+    pub fn all(&self) -> Vec<Range<usize>> {
+        let mut all = vec![self.try_.clone(), self.handler.clone()];
+        all.extend(self.filter.clone());
+        all
+    }
+}
+
+/// Clauses of all sections, small ones converted to fat.
+pub(crate) fn clauses(method: &Method) -> Vec<FatSectionClause> {
+    method
+        .sections
+        .iter()
+        .flat_map(|section| match section {
+            Section::FatSection(_, clauses) => clauses.clone(),
+            Section::SmallSection(_, clauses) => {
+                clauses.iter().map(FatSectionClause::from).collect()
+            }
+        })
+        .collect()
+}
+
+/// Whether the instruction empties the evaluation stack before branching.
+pub(crate) fn is_leave(instruction: &Instruction) -> bool {
+    instruction.opcode == LEAVE || instruction.opcode == LEAVE_S
+}
@@ -70,3 +70,51 @@ pub fn check_flag(flags: u8, flag: u8) -> bool {
 pub fn nearest_multiple(multiple: usize, value: usize) -> usize {
     value + (multiple - 1) & !(multiple - 1)
 }
+/// Reads an ECMA-335 compressed unsigned integer, returning it with its length in bytes.
+pub fn il_compressed_u32(il: &[u8], index: usize) -> Result<(u32, usize), Error> {
+    let byte_1 = il_u8(il, index)?;
+    match byte_1 {
+        0x00..=0x7F => Ok((byte_1 as u32, 1)),
+        0x80..=0xBF => {
+            let byte_2 = il_u8(il, index + 1)?;
+            Ok((u32::from_be_bytes([0, 0, byte_1 & 0x3F, byte_2]), 2))
+        }
+        0xC0..=0xDF => {
+            let byte_2 = il_u8(il, index + 1)?;
+            let byte_3 = il_u8(il, index + 2)?;
+            let byte_4 = il_u8(il, index + 3)?;
+            Ok((
+                u32::from_be_bytes([byte_1 & 0x1F, byte_2, byte_3, byte_4]),
+                4,
+            ))
+        }
+        _ => Err(Error::InvalidSignature),
+    }
+}
+/// Encodes an ECMA-335 compressed unsigned integer, which holds at most 29 bits.
+pub fn compress_u32(value: u32) -> Result<Vec<u8>, Error> {
+    match value {
+        0x00..=0x7F => Ok(vec![value as u8]),
+        0x80..=0x3FFF => Ok(vec![0x80 | (value >> 8) as u8, value as u8]),
+        0x4000..=0x1FFF_FFFF => Ok((value | 0xC000_0000).to_be_bytes().to_vec()),
+        _ => Err(Error::InvalidSignature),
+    }
+}
+/// Encodes an ECMA-335 compressed signed integer, which holds at most 29 bits.
+/// The value is rotated so its sign ends up in bit 0.
+pub fn compress_i32(value: i32) -> Result<Vec<u8>, Error> {
+    let rotated = |bits: u32| {
+        let mask = (1 << bits) - 1;
+        let value = value as u32 & mask;
+        (value << 1 | value >> (bits - 1)) & mask
+    };
+    match value {
+        -0x40..=0x3F => Ok(vec![rotated(7) as u8]),
+        -0x2000..=0x1FFF => {
+            let value = rotated(14);
+            Ok(vec![0x80 | (value >> 8) as u8, value as u8])
+        }
+        -0x1000_0000..=0x0FFF_FFFF => Ok((rotated(29) | 0xC000_0000).to_be_bytes().to_vec()),
+        _ => Err(Error::InvalidSignature),
+    }
+}
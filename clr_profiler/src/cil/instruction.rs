@@ -1,8 +1,9 @@
 use crate::cil::{
-    il_f32, il_f64, il_i32, il_i64, il_i8, il_u16, il_u32, il_u8, opcode::*, Error, OperandParams,
+    il_f32, il_f64, il_i32, il_i64, il_i8, il_u16, il_u32, il_u8, opcode::*, Error, Label,
+    OpcodeKind, OperandParams, Token,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Operand {
     InlineNone,
     ShortInlineVar(u8),
@@ -12,17 +13,24 @@ pub enum Operand {
     InlineI8(i64),
     ShortInlineR(f32),
     InlineR(f64),
-    InlineMethod(u32),
+    InlineMethod(Token),
     InlineSig(u32),
     ShortInlineBrTarget(i8),
     InlineBrTarget(i32),
     InlineSwitch(u32, Vec<i32>),
-    InlineType(u32),
-    InlineString(u32),
-    InlineField(u32),
-    InlineTok(u32),
+    InlineType(Token),
+    InlineString(Token),
+    InlineField(Token),
+    InlineTok(Token),
+    /// Branch target given as a label instead of a relative offset. Whether it
+    /// is encoded in 1 or 4 bytes depends on the opcode it is paired with.
+    BrTarget(Label),
+    /// Switch jump table given as labels instead of relative offsets.
+    SwitchTargets(Vec<Label>),
 }
 impl Operand {
+    /// Encoded length of the operand. A `BrTarget` always reports the long
+    /// (4 byte) form, use `Instruction::length` to account for short branches.
     pub fn length(&self) -> usize {
         match self {
             Self::InlineNone => 0,
@@ -42,16 +50,94 @@ impl Operand {
             Self::InlineString(_) => 4,
             Self::InlineField(_) => 4,
             Self::InlineTok(_) => 4,
+            Self::BrTarget(_) => 4,
+            Self::SwitchTargets(targets) => (targets.len() + 1) * 4,
+        }
+    }
+    /// Labels this operand branches to, if any.
+    pub fn targets(&self) -> Vec<Label> {
+        match self {
+            Self::BrTarget(target) => vec![*target],
+            Self::SwitchTargets(targets) => targets.clone(),
+            _ => Vec::new(),
+        }
+    }
+    /// Metadata token this operand refers to, if any. Stand-alone signatures
+    /// of `calli` aren't typed and aren't reported.
+    pub fn token(&self) -> Option<Token> {
+        match self {
+            Self::InlineMethod(token)
+            | Self::InlineType(token)
+            | Self::InlineString(token)
+            | Self::InlineField(token)
+            | Self::InlineTok(token) => Some(*token),
+            _ => None,
         }
     }
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Instruction {
     pub opcode: Opcode,
     pub operand: Operand,
+    /// Marks this instruction as the destination of branches and exception
+    /// clause boundaries that refer to the label.
+    pub label: Option<Label>,
+    /// Offset of the instruction in the body it was parsed from, `None` for
+    /// injected instructions. Used to map original offsets to rewritten ones.
+    pub original_offset: Option<u32>,
 }
 
 impl Instruction {
+    pub fn new(opcode: Opcode, operand: Operand) -> Self {
+        Instruction {
+            opcode,
+            operand,
+            label: None,
+            original_offset: None,
+        }
+    }
+    /// Instruction of an opcode taking a metadata token. Returns
+    /// `Error::InvalidToken` if the opcode can't refer to the token's table,
+    /// e.g. `call` of a type definition.
+    pub fn with_token(opcode: Opcode, token: Token) -> Result<Self, Error> {
+        let operand = match opcode.operand_params {
+            _ if !token.fits(&opcode.operand_params) => None,
+            OperandParams::InlineMethod => Some(Operand::InlineMethod(token)),
+            OperandParams::InlineType => Some(Operand::InlineType(token)),
+            OperandParams::InlineString => Some(Operand::InlineString(token)),
+            OperandParams::InlineField => Some(Operand::InlineField(token)),
+            OperandParams::InlineTok => Some(Operand::InlineTok(token)),
+            _ => None,
+        };
+        let operand = operand.ok_or(Error::InvalidToken(token.raw()))?;
+        Ok(Instruction::new(opcode, operand))
+    }
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.label = Some(label);
+        self
+    }
+    /// `ldloc` of the local at `index`, in its shortest encoding.
+    pub fn load_local(index: u16) -> Self {
+        match index {
+            0 => Instruction::new(LDLOC_0, Operand::InlineNone),
+            1 => Instruction::new(LDLOC_1, Operand::InlineNone),
+            2 => Instruction::new(LDLOC_2, Operand::InlineNone),
+            3 => Instruction::new(LDLOC_3, Operand::InlineNone),
+            4..=255 => Instruction::new(LDLOC_S, Operand::ShortInlineVar(index as u8)),
+            _ => Instruction::new(LDLOC, Operand::InlineVar(index)),
+        }
+    }
+    /// `stloc` to the local at `index`, in its shortest encoding.
+    pub fn store_local(index: u16) -> Self {
+        match index {
+            0 => Instruction::new(STLOC_0, Operand::InlineNone),
+            1 => Instruction::new(STLOC_1, Operand::InlineNone),
+            2 => Instruction::new(STLOC_2, Operand::InlineNone),
+            3 => Instruction::new(STLOC_3, Operand::InlineNone),
+            4..=255 => Instruction::new(STLOC_S, Operand::ShortInlineVar(index as u8)),
+            _ => Instruction::new(STLOC, Operand::InlineVar(index)),
+        }
+    }
     /// Attempts to parse the first instruction at the beginning
     /// of the given byte array. Array must be at a valid instruction
     /// boundary.
@@ -64,7 +150,17 @@ impl Instruction {
         } else {
             Ok(Opcode::from_byte(byte_1))
         }?;
+        if opcode.name == "unused" || opcode.opcode_kind == OpcodeKind::Internal {
+            return Err(Error::InvalidCilOpcode);
+        }
         let operand_index = opcode.length as usize;
+        let token = || -> Result<Token, Error> {
+            let token = Token::from_raw(il_u32(il, operand_index)?)?;
+            match token.fits(&opcode.operand_params) {
+                true => Ok(token),
+                false => Err(Error::InvalidToken(token.raw())),
+            }
+        };
         let operand = match &opcode.operand_params {
             OperandParams::InlineNone => Operand::InlineNone,
             OperandParams::ShortInlineVar => {
@@ -95,10 +191,7 @@ impl Instruction {
                 let val = il_f64(il, operand_index)?;
                 Operand::InlineR(val)
             }
-            OperandParams::InlineMethod => {
-                let val = il_u32(il, operand_index)?;
-                Operand::InlineMethod(val)
-            }
+            OperandParams::InlineMethod => Operand::InlineMethod(token()?),
             OperandParams::InlineSig => {
                 let val = il_u32(il, operand_index)?;
                 Operand::InlineSig(val)
@@ -113,6 +206,11 @@ impl Instruction {
             }
             OperandParams::InlineSwitch => {
                 let length = il_u32(il, operand_index)?;
+                // Don't trust the length to allocate before checking the targets are there
+                let available = il.len().saturating_sub(operand_index + 4) / 4;
+                if length as usize > available {
+                    return Err(Error::InvalidCil);
+                }
                 let mut val: Vec<i32> = Vec::with_capacity(length as usize);
                 for i in 1..=length {
                     let target_index = operand_index + ((i * 4) as usize);
@@ -121,27 +219,12 @@ impl Instruction {
                 }
                 Operand::InlineSwitch(length, val)
             }
-            OperandParams::InlineType => {
-                let val = il_u32(il, operand_index)?;
-                Operand::InlineType(val)
-            }
-            OperandParams::InlineString => {
-                let val = il_u32(il, operand_index)?;
-                Operand::InlineString(val)
-            }
-            OperandParams::InlineField => {
-                let val = il_u32(il, operand_index)?;
-                Operand::InlineField(val)
-            }
-            OperandParams::InlineTok => {
-                let val = il_u32(il, operand_index)?;
-                Operand::InlineTok(val)
-            }
+            OperandParams::InlineType => Operand::InlineType(token()?),
+            OperandParams::InlineString => Operand::InlineString(token()?),
+            OperandParams::InlineField => Operand::InlineField(token()?),
+            OperandParams::InlineTok => Operand::InlineTok(token()?),
         };
-        Ok(Instruction {
-            opcode: opcode,
-            operand: operand,
-        })
+        Ok(Instruction::new(opcode, operand))
     }
     pub fn into_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
@@ -160,1345 +243,693 @@ impl Instruction {
             Operand::InlineI8(val) => bytes.extend_from_slice(&val.to_le_bytes()),
             Operand::ShortInlineR(val) => bytes.extend_from_slice(&val.to_le_bytes()),
             Operand::InlineR(val) => bytes.extend_from_slice(&val.to_le_bytes()),
-            Operand::InlineMethod(val) => bytes.extend_from_slice(&val.to_le_bytes()),
+            Operand::InlineMethod(val) => bytes.extend_from_slice(&val.raw().to_le_bytes()),
             Operand::InlineSig(val) => bytes.extend_from_slice(&val.to_le_bytes()),
             Operand::ShortInlineBrTarget(val) => bytes.extend_from_slice(&val.to_le_bytes()),
             Operand::InlineBrTarget(val) => bytes.extend_from_slice(&val.to_le_bytes()),
             Operand::InlineSwitch(length, val) => {
                 bytes.extend_from_slice(&length.to_le_bytes());
-                println!(
-                    "{}!!! {}!!! {:?}!!! {:?}!!! {:?}!!! {:?}!!! {:?}!!! {:?}!!!",
-                    length,
-                    val.len(),
-                    length.to_le_bytes(),
-                    val[0].to_le_bytes(),
-                    val[1].to_le_bytes(),
-                    val[2].to_le_bytes(),
-                    val[3].to_le_bytes(),
-                    val[4].to_le_bytes(),
-                );
                 let mut target_bytes: Vec<u8> =
                     val.iter().flat_map(|s| s.to_le_bytes().to_vec()).collect();
                 bytes.append(&mut target_bytes);
             }
-            Operand::InlineType(val) => bytes.extend_from_slice(&val.to_le_bytes()),
-            Operand::InlineString(val) => bytes.extend_from_slice(&val.to_le_bytes()),
-            Operand::InlineField(val) => bytes.extend_from_slice(&val.to_le_bytes()),
-            Operand::InlineTok(val) => bytes.extend_from_slice(&val.to_le_bytes()),
+            Operand::InlineType(val) => bytes.extend_from_slice(&val.raw().to_le_bytes()),
+            Operand::InlineString(val) => bytes.extend_from_slice(&val.raw().to_le_bytes()),
+            Operand::InlineField(val) => bytes.extend_from_slice(&val.raw().to_le_bytes()),
+            Operand::InlineTok(val) => bytes.extend_from_slice(&val.raw().to_le_bytes()),
+            // Labels can only be resolved against a whole method body (see
+            // `Method::into_bytes`), so only reserve the space for them here.
+            Operand::BrTarget(_) => bytes.resize(bytes.len() + self.operand_length(), 0),
+            Operand::SwitchTargets(targets) => {
+                bytes.extend_from_slice(&(targets.len() as u32).to_le_bytes());
+                bytes.resize(bytes.len() + targets.len() * 4, 0);
+            }
         }
 
         bytes
     }
     pub fn length(&self) -> usize {
-        self.opcode.length as usize + self.operand.length()
+        self.opcode.length as usize + self.operand_length()
+    }
+    fn operand_length(&self) -> usize {
+        match (&self.operand, &self.opcode.operand_params) {
+            (Operand::BrTarget(_), OperandParams::ShortInlineBrTarget) => 1,
+            (operand, _) => operand.length(),
+        }
     }
 }
 
 pub fn nop() -> Instruction {
-    Instruction {
-        opcode: NOP,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(NOP, Operand::InlineNone)
 }
 pub fn break_() -> Instruction {
-    Instruction {
-        opcode: BREAK,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(BREAK, Operand::InlineNone)
 }
 pub fn ldarg_0() -> Instruction {
-    Instruction {
-        opcode: LDARG_0,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDARG_0, Operand::InlineNone)
 }
 pub fn ldarg_1() -> Instruction {
-    Instruction {
-        opcode: LDARG_1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDARG_1, Operand::InlineNone)
 }
 pub fn ldarg_2() -> Instruction {
-    Instruction {
-        opcode: LDARG_2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDARG_2, Operand::InlineNone)
 }
 pub fn ldarg_3() -> Instruction {
-    Instruction {
-        opcode: LDARG_3,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDARG_3, Operand::InlineNone)
 }
 pub fn ldloc_0() -> Instruction {
-    Instruction {
-        opcode: LDLOC_0,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDLOC_0, Operand::InlineNone)
 }
 pub fn ldloc_1() -> Instruction {
-    Instruction {
-        opcode: LDLOC_1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDLOC_1, Operand::InlineNone)
 }
 pub fn ldloc_2() -> Instruction {
-    Instruction {
-        opcode: LDLOC_2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDLOC_2, Operand::InlineNone)
 }
 pub fn ldloc_3() -> Instruction {
-    Instruction {
-        opcode: LDLOC_3,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDLOC_3, Operand::InlineNone)
 }
 pub fn stloc_0() -> Instruction {
-    Instruction {
-        opcode: STLOC_0,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STLOC_0, Operand::InlineNone)
 }
 pub fn stloc_1() -> Instruction {
-    Instruction {
-        opcode: STLOC_1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STLOC_1, Operand::InlineNone)
 }
 pub fn stloc_2() -> Instruction {
-    Instruction {
-        opcode: STLOC_2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STLOC_2, Operand::InlineNone)
 }
 pub fn stloc_3() -> Instruction {
-    Instruction {
-        opcode: STLOC_3,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STLOC_3, Operand::InlineNone)
 }
 pub fn ldarg_s(val: u8) -> Instruction {
-    Instruction {
-        opcode: LDARG_S,
-        operand: Operand::ShortInlineVar(val),
-    }
+    Instruction::new(LDARG_S, Operand::ShortInlineVar(val))
 }
 pub fn ldarga_s(val: u8) -> Instruction {
-    Instruction {
-        opcode: LDARGA_S,
-        operand: Operand::ShortInlineVar(val),
-    }
+    Instruction::new(LDARGA_S, Operand::ShortInlineVar(val))
 }
 pub fn starg_s(val: u8) -> Instruction {
-    Instruction {
-        opcode: STARG_S,
-        operand: Operand::ShortInlineVar(val),
-    }
+    Instruction::new(STARG_S, Operand::ShortInlineVar(val))
 }
 pub fn ldloc_s(val: u8) -> Instruction {
-    Instruction {
-        opcode: LDLOC_S,
-        operand: Operand::ShortInlineVar(val),
-    }
+    Instruction::new(LDLOC_S, Operand::ShortInlineVar(val))
 }
 pub fn ldloca_s(val: u8) -> Instruction {
-    Instruction {
-        opcode: LDLOCA_S,
-        operand: Operand::ShortInlineVar(val),
-    }
+    Instruction::new(LDLOCA_S, Operand::ShortInlineVar(val))
 }
 pub fn stloc_s(val: u8) -> Instruction {
-    Instruction {
-        opcode: STLOC_S,
-        operand: Operand::ShortInlineVar(val),
-    }
+    Instruction::new(STLOC_S, Operand::ShortInlineVar(val))
 }
 pub fn ldnull() -> Instruction {
-    Instruction {
-        opcode: LDNULL,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDNULL, Operand::InlineNone)
 }
 pub fn ldc_i4_m1() -> Instruction {
-    Instruction {
-        opcode: LDC_I4_M1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDC_I4_M1, Operand::InlineNone)
 }
 pub fn ldc_i4_0() -> Instruction {
-    Instruction {
-        opcode: LDC_I4_0,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDC_I4_0, Operand::InlineNone)
 }
 pub fn ldc_i4_1() -> Instruction {
-    Instruction {
-        opcode: LDC_I4_1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDC_I4_1, Operand::InlineNone)
 }
 pub fn ldc_i4_2() -> Instruction {
-    Instruction {
-        opcode: LDC_I4_2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDC_I4_2, Operand::InlineNone)
 }
 pub fn ldc_i4_3() -> Instruction {
-    Instruction {
-        opcode: LDC_I4_3,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDC_I4_3, Operand::InlineNone)
 }
 pub fn ldc_i4_4() -> Instruction {
-    Instruction {
-        opcode: LDC_I4_4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDC_I4_4, Operand::InlineNone)
 }
 pub fn ldc_i4_5() -> Instruction {
-    Instruction {
-        opcode: LDC_I4_5,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDC_I4_5, Operand::InlineNone)
 }
 pub fn ldc_i4_6() -> Instruction {
-    Instruction {
-        opcode: LDC_I4_6,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDC_I4_6, Operand::InlineNone)
 }
 pub fn ldc_i4_7() -> Instruction {
-    Instruction {
-        opcode: LDC_I4_7,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDC_I4_7, Operand::InlineNone)
 }
 pub fn ldc_i4_8() -> Instruction {
-    Instruction {
-        opcode: LDC_I4_8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDC_I4_8, Operand::InlineNone)
 }
 pub fn ldc_i4_s(val: u8) -> Instruction {
-    Instruction {
-        opcode: LDC_I4_S,
-        operand: Operand::ShortInlineI(val),
-    }
+    Instruction::new(LDC_I4_S, Operand::ShortInlineI(val))
 }
 pub fn ldc_i4(val: i32) -> Instruction {
-    Instruction {
-        opcode: LDC_I4,
-        operand: Operand::InlineI(val),
-    }
+    Instruction::new(LDC_I4, Operand::InlineI(val))
 }
 pub fn ldc_i8(val: i64) -> Instruction {
-    Instruction {
-        opcode: LDC_I8,
-        operand: Operand::InlineI8(val),
-    }
+    Instruction::new(LDC_I8, Operand::InlineI8(val))
 }
 pub fn ldc_r4(val: f32) -> Instruction {
-    Instruction {
-        opcode: LDC_R4,
-        operand: Operand::ShortInlineR(val),
-    }
+    Instruction::new(LDC_R4, Operand::ShortInlineR(val))
 }
 pub fn ldc_r8(val: f64) -> Instruction {
-    Instruction {
-        opcode: LDC_R8,
-        operand: Operand::InlineR(val),
-    }
+    Instruction::new(LDC_R8, Operand::InlineR(val))
 }
 pub fn dup() -> Instruction {
-    Instruction {
-        opcode: DUP,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(DUP, Operand::InlineNone)
 }
 pub fn pop() -> Instruction {
-    Instruction {
-        opcode: POP,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(POP, Operand::InlineNone)
 }
-pub fn jmp(val: u32) -> Instruction {
-    Instruction {
-        opcode: JMP,
-        operand: Operand::InlineMethod(val),
-    }
+pub fn jmp(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(JMP, val)
 }
-pub fn call(val: u32) -> Instruction {
-    Instruction {
-        opcode: CALL,
-        operand: Operand::InlineMethod(val),
-    }
+pub fn call(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(CALL, val)
 }
 pub fn calli(val: u32) -> Instruction {
-    Instruction {
-        opcode: CALLI,
-        operand: Operand::InlineSig(val),
-    }
+    Instruction::new(CALLI, Operand::InlineSig(val))
 }
 pub fn ret() -> Instruction {
-    Instruction {
-        opcode: RET,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(RET, Operand::InlineNone)
 }
 pub fn br_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BR_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BR_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn brfalse_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BRFALSE_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BRFALSE_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn brtrue_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BRTRUE_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BRTRUE_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn beq_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BEQ_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BEQ_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn bge_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BGE_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BGE_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn bgt_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BGT_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BGT_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn ble_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BLE_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BLE_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn blt_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BLT_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BLT_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn bne_un_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BNE_UN_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BNE_UN_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn bge_un_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BGE_UN_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BGE_UN_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn bgt_un_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BGT_UN_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BGT_UN_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn ble_un_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BLE_UN_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BLE_UN_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn blt_un_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: BLT_UN_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(BLT_UN_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn br(val: i32) -> Instruction {
-    Instruction {
-        opcode: BR,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BR, Operand::InlineBrTarget(val))
 }
 pub fn brfalse(val: i32) -> Instruction {
-    Instruction {
-        opcode: BRFALSE,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BRFALSE, Operand::InlineBrTarget(val))
 }
 pub fn brtrue(val: i32) -> Instruction {
-    Instruction {
-        opcode: BRTRUE,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BRTRUE, Operand::InlineBrTarget(val))
 }
 pub fn beq(val: i32) -> Instruction {
-    Instruction {
-        opcode: BEQ,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BEQ, Operand::InlineBrTarget(val))
 }
 pub fn bge(val: i32) -> Instruction {
-    Instruction {
-        opcode: BGE,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BGE, Operand::InlineBrTarget(val))
 }
 pub fn bgt(val: i32) -> Instruction {
-    Instruction {
-        opcode: BGT,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BGT, Operand::InlineBrTarget(val))
 }
 pub fn ble(val: i32) -> Instruction {
-    Instruction {
-        opcode: BLE,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BLE, Operand::InlineBrTarget(val))
 }
 pub fn blt(val: i32) -> Instruction {
-    Instruction {
-        opcode: BLT,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BLT, Operand::InlineBrTarget(val))
 }
 pub fn bne_un(val: i32) -> Instruction {
-    Instruction {
-        opcode: BNE_UN,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BNE_UN, Operand::InlineBrTarget(val))
 }
 pub fn bge_un(val: i32) -> Instruction {
-    Instruction {
-        opcode: BGE_UN,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BGE_UN, Operand::InlineBrTarget(val))
 }
 pub fn bgt_un(val: i32) -> Instruction {
-    Instruction {
-        opcode: BGT_UN,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BGT_UN, Operand::InlineBrTarget(val))
 }
 pub fn ble_un(val: i32) -> Instruction {
-    Instruction {
-        opcode: BLE_UN,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BLE_UN, Operand::InlineBrTarget(val))
 }
 pub fn blt_un(val: i32) -> Instruction {
-    Instruction {
-        opcode: BLT_UN,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(BLT_UN, Operand::InlineBrTarget(val))
 }
 pub fn switch(length: u32, targets: Vec<i32>) -> Instruction {
-    Instruction {
-        opcode: SWITCH,
-        operand: Operand::InlineSwitch(length, targets),
-    }
+    Instruction::new(SWITCH, Operand::InlineSwitch(length, targets))
 }
 pub fn ldind_i1() -> Instruction {
-    Instruction {
-        opcode: LDIND_I1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDIND_I1, Operand::InlineNone)
 }
 pub fn ldind_u1() -> Instruction {
-    Instruction {
-        opcode: LDIND_U1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDIND_U1, Operand::InlineNone)
 }
 pub fn ldind_i2() -> Instruction {
-    Instruction {
-        opcode: LDIND_I2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDIND_I2, Operand::InlineNone)
 }
 pub fn ldind_u2() -> Instruction {
-    Instruction {
-        opcode: LDIND_U2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDIND_U2, Operand::InlineNone)
 }
 pub fn ldind_i4() -> Instruction {
-    Instruction {
-        opcode: LDIND_I4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDIND_I4, Operand::InlineNone)
 }
 pub fn ldind_u4() -> Instruction {
-    Instruction {
-        opcode: LDIND_U4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDIND_U4, Operand::InlineNone)
 }
 pub fn ldind_i8() -> Instruction {
-    Instruction {
-        opcode: LDIND_I8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDIND_I8, Operand::InlineNone)
 }
 pub fn ldind_i() -> Instruction {
-    Instruction {
-        opcode: LDIND_I,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDIND_I, Operand::InlineNone)
 }
 pub fn ldind_r4() -> Instruction {
-    Instruction {
-        opcode: LDIND_R4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDIND_R4, Operand::InlineNone)
 }
 pub fn ldind_r8() -> Instruction {
-    Instruction {
-        opcode: LDIND_R8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDIND_R8, Operand::InlineNone)
 }
 pub fn ldind_ref() -> Instruction {
-    Instruction {
-        opcode: LDIND_REF,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDIND_REF, Operand::InlineNone)
 }
 pub fn stind_ref() -> Instruction {
-    Instruction {
-        opcode: STIND_REF,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STIND_REF, Operand::InlineNone)
 }
 pub fn stind_i1() -> Instruction {
-    Instruction {
-        opcode: STIND_I1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STIND_I1, Operand::InlineNone)
 }
 pub fn stind_i2() -> Instruction {
-    Instruction {
-        opcode: STIND_I2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STIND_I2, Operand::InlineNone)
 }
 pub fn stind_i4() -> Instruction {
-    Instruction {
-        opcode: STIND_I4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STIND_I4, Operand::InlineNone)
 }
 pub fn stind_i8() -> Instruction {
-    Instruction {
-        opcode: STIND_I8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STIND_I8, Operand::InlineNone)
 }
 pub fn stind_r4() -> Instruction {
-    Instruction {
-        opcode: STIND_R4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STIND_R4, Operand::InlineNone)
 }
 pub fn stind_r8() -> Instruction {
-    Instruction {
-        opcode: STIND_R8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STIND_R8, Operand::InlineNone)
 }
 pub fn add() -> Instruction {
-    Instruction {
-        opcode: ADD,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(ADD, Operand::InlineNone)
 }
 pub fn sub() -> Instruction {
-    Instruction {
-        opcode: SUB,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(SUB, Operand::InlineNone)
 }
 pub fn mul() -> Instruction {
-    Instruction {
-        opcode: MUL,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(MUL, Operand::InlineNone)
 }
 pub fn div() -> Instruction {
-    Instruction {
-        opcode: DIV,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(DIV, Operand::InlineNone)
 }
 pub fn div_un() -> Instruction {
-    Instruction {
-        opcode: DIV_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(DIV_UN, Operand::InlineNone)
 }
 pub fn rem() -> Instruction {
-    Instruction {
-        opcode: REM,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(REM, Operand::InlineNone)
 }
 pub fn rem_un() -> Instruction {
-    Instruction {
-        opcode: REM_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(REM_UN, Operand::InlineNone)
 }
 pub fn and() -> Instruction {
-    Instruction {
-        opcode: AND,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(AND, Operand::InlineNone)
 }
 pub fn or() -> Instruction {
-    Instruction {
-        opcode: OR,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(OR, Operand::InlineNone)
 }
 pub fn xor() -> Instruction {
-    Instruction {
-        opcode: XOR,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(XOR, Operand::InlineNone)
 }
 pub fn shl() -> Instruction {
-    Instruction {
-        opcode: SHL,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(SHL, Operand::InlineNone)
 }
 pub fn shr() -> Instruction {
-    Instruction {
-        opcode: SHR,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(SHR, Operand::InlineNone)
 }
 pub fn shr_un() -> Instruction {
-    Instruction {
-        opcode: SHR_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(SHR_UN, Operand::InlineNone)
 }
 pub fn neg() -> Instruction {
-    Instruction {
-        opcode: NEG,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(NEG, Operand::InlineNone)
 }
 pub fn not() -> Instruction {
-    Instruction {
-        opcode: NOT,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(NOT, Operand::InlineNone)
 }
 pub fn conv_i1() -> Instruction {
-    Instruction {
-        opcode: CONV_I1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_I1, Operand::InlineNone)
 }
 pub fn conv_i2() -> Instruction {
-    Instruction {
-        opcode: CONV_I2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_I2, Operand::InlineNone)
 }
 pub fn conv_i4() -> Instruction {
-    Instruction {
-        opcode: CONV_I4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_I4, Operand::InlineNone)
 }
 pub fn conv_i8() -> Instruction {
-    Instruction {
-        opcode: CONV_I8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_I8, Operand::InlineNone)
 }
 pub fn conv_r4() -> Instruction {
-    Instruction {
-        opcode: CONV_R4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_R4, Operand::InlineNone)
 }
 pub fn conv_r8() -> Instruction {
-    Instruction {
-        opcode: CONV_R8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_R8, Operand::InlineNone)
 }
 pub fn conv_u4() -> Instruction {
-    Instruction {
-        opcode: CONV_U4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_U4, Operand::InlineNone)
 }
 pub fn conv_u8() -> Instruction {
-    Instruction {
-        opcode: CONV_U8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_U8, Operand::InlineNone)
 }
-pub fn callvirt(val: u32) -> Instruction {
-    Instruction {
-        opcode: CALLVIRT,
-        operand: Operand::InlineMethod(val),
-    }
+pub fn callvirt(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(CALLVIRT, val)
 }
-pub fn cpobj(val: u32) -> Instruction {
-    Instruction {
-        opcode: CPOBJ,
-        operand: Operand::InlineType(val),
-    }
+pub fn cpobj(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(CPOBJ, val)
 }
-pub fn ldobj(val: u32) -> Instruction {
-    Instruction {
-        opcode: LDOBJ,
-        operand: Operand::InlineType(val),
-    }
+pub fn ldobj(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(LDOBJ, val)
 }
-pub fn ldstr(val: u32) -> Instruction {
-    Instruction {
-        opcode: LDSTR,
-        operand: Operand::InlineString(val),
-    }
+pub fn ldstr(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(LDSTR, val)
 }
-pub fn newobj(val: u32) -> Instruction {
-    Instruction {
-        opcode: NEWOBJ,
-        operand: Operand::InlineMethod(val),
-    }
+pub fn newobj(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(NEWOBJ, val)
 }
-pub fn castclass(val: u32) -> Instruction {
-    Instruction {
-        opcode: CASTCLASS,
-        operand: Operand::InlineType(val),
-    }
+pub fn castclass(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(CASTCLASS, val)
 }
-pub fn isinst(val: u32) -> Instruction {
-    Instruction {
-        opcode: ISINST,
-        operand: Operand::InlineType(val),
-    }
+pub fn isinst(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(ISINST, val)
 }
 pub fn conv_r_un() -> Instruction {
-    Instruction {
-        opcode: CONV_R_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_R_UN, Operand::InlineNone)
 }
-pub fn unbox(val: u32) -> Instruction {
-    Instruction {
-        opcode: UNBOX,
-        operand: Operand::InlineType(val),
-    }
+pub fn unbox(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(UNBOX, val)
 }
 pub fn throw() -> Instruction {
-    Instruction {
-        opcode: THROW,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(THROW, Operand::InlineNone)
 }
-pub fn ldfld(val: u32) -> Instruction {
-    Instruction {
-        opcode: LDFLD,
-        operand: Operand::InlineField(val),
-    }
+pub fn ldfld(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(LDFLD, val)
 }
-pub fn ldflda(val: u32) -> Instruction {
-    Instruction {
-        opcode: LDFLDA,
-        operand: Operand::InlineField(val),
-    }
+pub fn ldflda(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(LDFLDA, val)
 }
-pub fn stfld(val: u32) -> Instruction {
-    Instruction {
-        opcode: STFLD,
-        operand: Operand::InlineField(val),
-    }
+pub fn stfld(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(STFLD, val)
 }
-pub fn ldsfld(val: u32) -> Instruction {
-    Instruction {
-        opcode: LDSFLD,
-        operand: Operand::InlineField(val),
-    }
+pub fn ldsfld(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(LDSFLD, val)
 }
-pub fn ldsflda(val: u32) -> Instruction {
-    Instruction {
-        opcode: LDSFLDA,
-        operand: Operand::InlineField(val),
-    }
+pub fn ldsflda(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(LDSFLDA, val)
 }
-pub fn stsfld(val: u32) -> Instruction {
-    Instruction {
-        opcode: STSFLD,
-        operand: Operand::InlineField(val),
-    }
+pub fn stsfld(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(STSFLD, val)
 }
-pub fn stobj(val: u32) -> Instruction {
-    Instruction {
-        opcode: STOBJ,
-        operand: Operand::InlineType(val),
-    }
+pub fn stobj(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(STOBJ, val)
 }
 pub fn conv_ovf_i1_un() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_I1_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_I1_UN, Operand::InlineNone)
 }
 pub fn conv_ovf_i2_un() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_I2_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_I2_UN, Operand::InlineNone)
 }
 pub fn conv_ovf_i4_un() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_I4_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_I4_UN, Operand::InlineNone)
 }
 pub fn conv_ovf_i8_un() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_I8_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_I8_UN, Operand::InlineNone)
 }
 pub fn conv_ovf_u1_un() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_U1_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_U1_UN, Operand::InlineNone)
 }
 pub fn conv_ovf_u2_un() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_U2_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_U2_UN, Operand::InlineNone)
 }
 pub fn conv_ovf_u4_un() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_U4_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_U4_UN, Operand::InlineNone)
 }
 pub fn conv_ovf_u8_un() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_U8_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_U8_UN, Operand::InlineNone)
 }
 pub fn conv_ovf_i_un() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_I_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_I_UN, Operand::InlineNone)
 }
 pub fn conv_ovf_u_un() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_U_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_U_UN, Operand::InlineNone)
 }
-pub fn box_(val: u32) -> Instruction {
-    Instruction {
-        opcode: BOX,
-        operand: Operand::InlineType(val),
-    }
+pub fn box_(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(BOX, val)
 }
-pub fn newarr(val: u32) -> Instruction {
-    Instruction {
-        opcode: NEWARR,
-        operand: Operand::InlineType(val),
-    }
+pub fn newarr(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(NEWARR, val)
 }
 pub fn ldlen() -> Instruction {
-    Instruction {
-        opcode: LDLEN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDLEN, Operand::InlineNone)
 }
-pub fn ldelema(val: u32) -> Instruction {
-    Instruction {
-        opcode: LDELEMA,
-        operand: Operand::InlineType(val),
-    }
+pub fn ldelema(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(LDELEMA, val)
 }
 pub fn ldelem_i1() -> Instruction {
-    Instruction {
-        opcode: LDELEM_I1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDELEM_I1, Operand::InlineNone)
 }
 pub fn ldelem_u1() -> Instruction {
-    Instruction {
-        opcode: LDELEM_U1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDELEM_U1, Operand::InlineNone)
 }
 pub fn ldelem_i2() -> Instruction {
-    Instruction {
-        opcode: LDELEM_I2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDELEM_I2, Operand::InlineNone)
 }
 pub fn ldelem_u2() -> Instruction {
-    Instruction {
-        opcode: LDELEM_U2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDELEM_U2, Operand::InlineNone)
 }
 pub fn ldelem_i4() -> Instruction {
-    Instruction {
-        opcode: LDELEM_I4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDELEM_I4, Operand::InlineNone)
 }
 pub fn ldelem_u4() -> Instruction {
-    Instruction {
-        opcode: LDELEM_U4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDELEM_U4, Operand::InlineNone)
 }
 pub fn ldelem_i8() -> Instruction {
-    Instruction {
-        opcode: LDELEM_I8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDELEM_I8, Operand::InlineNone)
 }
 pub fn ldelem_i() -> Instruction {
-    Instruction {
-        opcode: LDELEM_I,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDELEM_I, Operand::InlineNone)
 }
 pub fn ldelem_r4() -> Instruction {
-    Instruction {
-        opcode: LDELEM_R4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDELEM_R4, Operand::InlineNone)
 }
 pub fn ldelem_r8() -> Instruction {
-    Instruction {
-        opcode: LDELEM_R8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDELEM_R8, Operand::InlineNone)
 }
 pub fn ldelem_ref() -> Instruction {
-    Instruction {
-        opcode: LDELEM_REF,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LDELEM_REF, Operand::InlineNone)
 }
 pub fn stelem_i() -> Instruction {
-    Instruction {
-        opcode: STELEM_I,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STELEM_I, Operand::InlineNone)
 }
 pub fn stelem_i1() -> Instruction {
-    Instruction {
-        opcode: STELEM_I1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STELEM_I1, Operand::InlineNone)
 }
 pub fn stelem_i2() -> Instruction {
-    Instruction {
-        opcode: STELEM_I2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STELEM_I2, Operand::InlineNone)
 }
 pub fn stelem_i4() -> Instruction {
-    Instruction {
-        opcode: STELEM_I4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STELEM_I4, Operand::InlineNone)
 }
 pub fn stelem_i8() -> Instruction {
-    Instruction {
-        opcode: STELEM_I8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STELEM_I8, Operand::InlineNone)
 }
 pub fn stelem_r4() -> Instruction {
-    Instruction {
-        opcode: STELEM_R4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STELEM_R4, Operand::InlineNone)
 }
 pub fn stelem_r8() -> Instruction {
-    Instruction {
-        opcode: STELEM_R8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STELEM_R8, Operand::InlineNone)
 }
 pub fn stelem_ref() -> Instruction {
-    Instruction {
-        opcode: STELEM_REF,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STELEM_REF, Operand::InlineNone)
 }
-pub fn ldelem(val: u32) -> Instruction {
-    Instruction {
-        opcode: LDELEM,
-        operand: Operand::InlineType(val),
-    }
+pub fn ldelem(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(LDELEM, val)
 }
-pub fn stelem(val: u32) -> Instruction {
-    Instruction {
-        opcode: STELEM,
-        operand: Operand::InlineType(val),
-    }
+pub fn stelem(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(STELEM, val)
 }
-pub fn unbox_any(val: u32) -> Instruction {
-    Instruction {
-        opcode: UNBOX_ANY,
-        operand: Operand::InlineType(val),
-    }
+pub fn unbox_any(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(UNBOX_ANY, val)
 }
 pub fn conv_ovf_i1() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_I1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_I1, Operand::InlineNone)
 }
 pub fn conv_ovf_u1() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_U1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_U1, Operand::InlineNone)
 }
 pub fn conv_ovf_i2() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_I2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_I2, Operand::InlineNone)
 }
 pub fn conv_ovf_u2() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_U2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_U2, Operand::InlineNone)
 }
 pub fn conv_ovf_i4() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_I4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_I4, Operand::InlineNone)
 }
 pub fn conv_ovf_u4() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_U4,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_U4, Operand::InlineNone)
 }
 pub fn conv_ovf_i8() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_I8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_I8, Operand::InlineNone)
 }
 pub fn conv_ovf_u8() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_U8,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_U8, Operand::InlineNone)
 }
-pub fn refanyval(val: u32) -> Instruction {
-    Instruction {
-        opcode: REFANYVAL,
-        operand: Operand::InlineType(val),
-    }
+pub fn refanyval(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(REFANYVAL, val)
 }
 pub fn ckfinite() -> Instruction {
-    Instruction {
-        opcode: CKFINITE,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CKFINITE, Operand::InlineNone)
 }
-pub fn mkrefany(val: u32) -> Instruction {
-    Instruction {
-        opcode: MKREFANY,
-        operand: Operand::InlineType(val),
-    }
+pub fn mkrefany(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(MKREFANY, val)
 }
-pub fn ldtoken(val: u32) -> Instruction {
-    Instruction {
-        opcode: LDTOKEN,
-        operand: Operand::InlineTok(val),
-    }
+pub fn ldtoken(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(LDTOKEN, val)
 }
 pub fn conv_u2() -> Instruction {
-    Instruction {
-        opcode: CONV_U2,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_U2, Operand::InlineNone)
 }
 pub fn conv_u1() -> Instruction {
-    Instruction {
-        opcode: CONV_U1,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_U1, Operand::InlineNone)
 }
 pub fn conv_i() -> Instruction {
-    Instruction {
-        opcode: CONV_I,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_I, Operand::InlineNone)
 }
 pub fn conv_ovf_i() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_I,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_I, Operand::InlineNone)
 }
 pub fn conv_ovf_u() -> Instruction {
-    Instruction {
-        opcode: CONV_OVF_U,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_OVF_U, Operand::InlineNone)
 }
 pub fn add_ovf() -> Instruction {
-    Instruction {
-        opcode: ADD_OVF,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(ADD_OVF, Operand::InlineNone)
 }
 pub fn add_ovf_un() -> Instruction {
-    Instruction {
-        opcode: ADD_OVF_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(ADD_OVF_UN, Operand::InlineNone)
 }
 pub fn mul_ovf() -> Instruction {
-    Instruction {
-        opcode: MUL_OVF,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(MUL_OVF, Operand::InlineNone)
 }
 pub fn mul_ovf_un() -> Instruction {
-    Instruction {
-        opcode: MUL_OVF_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(MUL_OVF_UN, Operand::InlineNone)
 }
 pub fn sub_ovf() -> Instruction {
-    Instruction {
-        opcode: SUB_OVF,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(SUB_OVF, Operand::InlineNone)
 }
 pub fn sub_ovf_un() -> Instruction {
-    Instruction {
-        opcode: SUB_OVF_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(SUB_OVF_UN, Operand::InlineNone)
 }
 pub fn endfinally() -> Instruction {
-    Instruction {
-        opcode: ENDFINALLY,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(ENDFINALLY, Operand::InlineNone)
 }
 pub fn leave(val: i32) -> Instruction {
-    Instruction {
-        opcode: LEAVE,
-        operand: Operand::InlineBrTarget(val),
-    }
+    Instruction::new(LEAVE, Operand::InlineBrTarget(val))
 }
 pub fn leave_s(val: i8) -> Instruction {
-    Instruction {
-        opcode: LEAVE_S,
-        operand: Operand::ShortInlineBrTarget(val),
-    }
+    Instruction::new(LEAVE_S, Operand::ShortInlineBrTarget(val))
 }
 pub fn stind_i() -> Instruction {
-    Instruction {
-        opcode: STIND_I,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(STIND_I, Operand::InlineNone)
 }
 pub fn conv_u() -> Instruction {
-    Instruction {
-        opcode: CONV_U,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CONV_U, Operand::InlineNone)
 }
 pub fn arglist() -> Instruction {
-    Instruction {
-        opcode: ARGLIST,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(ARGLIST, Operand::InlineNone)
 }
 pub fn ceq() -> Instruction {
-    Instruction {
-        opcode: CEQ,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CEQ, Operand::InlineNone)
 }
 pub fn cgt() -> Instruction {
-    Instruction {
-        opcode: CGT,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CGT, Operand::InlineNone)
 }
 pub fn cgt_un() -> Instruction {
-    Instruction {
-        opcode: CGT_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CGT_UN, Operand::InlineNone)
 }
 pub fn clt() -> Instruction {
-    Instruction {
-        opcode: CLT,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CLT, Operand::InlineNone)
 }
 pub fn clt_un() -> Instruction {
-    Instruction {
-        opcode: CLT_UN,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CLT_UN, Operand::InlineNone)
 }
-pub fn ldftn(val: u32) -> Instruction {
-    Instruction {
-        opcode: LDFTN,
-        operand: Operand::InlineMethod(val),
-    }
+pub fn ldftn(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(LDFTN, val)
 }
-pub fn ldvirtftn(val: u32) -> Instruction {
-    Instruction {
-        opcode: LDVIRTFTN,
-        operand: Operand::InlineMethod(val),
-    }
+pub fn ldvirtftn(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(LDVIRTFTN, val)
 }
 pub fn ldarg(val: u16) -> Instruction {
-    Instruction {
-        opcode: LDARG,
-        operand: Operand::InlineVar(val),
-    }
+    Instruction::new(LDARG, Operand::InlineVar(val))
 }
 pub fn ldarga(val: u16) -> Instruction {
-    Instruction {
-        opcode: LDARGA,
-        operand: Operand::InlineVar(val),
-    }
+    Instruction::new(LDARGA, Operand::InlineVar(val))
 }
 pub fn starg(val: u16) -> Instruction {
-    Instruction {
-        opcode: STARG,
-        operand: Operand::InlineVar(val),
-    }
+    Instruction::new(STARG, Operand::InlineVar(val))
 }
 pub fn ldloc(val: u16) -> Instruction {
-    Instruction {
-        opcode: LDLOC,
-        operand: Operand::InlineVar(val),
-    }
+    Instruction::new(LDLOC, Operand::InlineVar(val))
 }
 pub fn ldloca(val: u16) -> Instruction {
-    Instruction {
-        opcode: LDLOCA,
-        operand: Operand::InlineVar(val),
-    }
+    Instruction::new(LDLOCA, Operand::InlineVar(val))
 }
 pub fn stloc(val: u16) -> Instruction {
-    Instruction {
-        opcode: STLOC,
-        operand: Operand::InlineVar(val),
-    }
+    Instruction::new(STLOC, Operand::InlineVar(val))
 }
 pub fn localloc() -> Instruction {
-    Instruction {
-        opcode: LOCALLOC,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(LOCALLOC, Operand::InlineNone)
 }
 pub fn endfilter() -> Instruction {
-    Instruction {
-        opcode: ENDFILTER,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(ENDFILTER, Operand::InlineNone)
 }
 pub fn unaligned(val: u8) -> Instruction {
-    Instruction {
-        opcode: UNALIGNED,
-        operand: Operand::ShortInlineI(val),
-    }
+    Instruction::new(UNALIGNED, Operand::ShortInlineI(val))
 }
 pub fn volatile() -> Instruction {
-    Instruction {
-        opcode: VOLATILE,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(VOLATILE, Operand::InlineNone)
 }
 pub fn tailcall() -> Instruction {
-    Instruction {
-        opcode: TAILCALL,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(TAILCALL, Operand::InlineNone)
 }
-pub fn initobj(val: u32) -> Instruction {
-    Instruction {
-        opcode: INITOBJ,
-        operand: Operand::InlineType(val),
-    }
+pub fn initobj(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(INITOBJ, val)
 }
-pub fn constrained(val: u32) -> Instruction {
-    Instruction {
-        opcode: CONSTRAINED,
-        operand: Operand::InlineType(val),
-    }
+pub fn constrained(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(CONSTRAINED, val)
 }
 pub fn cpblk() -> Instruction {
-    Instruction {
-        opcode: CPBLK,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(CPBLK, Operand::InlineNone)
 }
 pub fn initblk() -> Instruction {
-    Instruction {
-        opcode: INITBLK,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(INITBLK, Operand::InlineNone)
 }
 pub fn rethrow() -> Instruction {
-    Instruction {
-        opcode: RETHROW,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(RETHROW, Operand::InlineNone)
 }
-pub fn sizeof(val: u32) -> Instruction {
-    Instruction {
-        opcode: SIZEOF,
-        operand: Operand::InlineType(val),
-    }
+pub fn sizeof(val: Token) -> Result<Instruction, Error> {
+    Instruction::with_token(SIZEOF, val)
 }
 pub fn refanytype() -> Instruction {
-    Instruction {
-        opcode: REFANYTYPE,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(REFANYTYPE, Operand::InlineNone)
 }
 pub fn readonly() -> Instruction {
-    Instruction {
-        opcode: READONLY,
-        operand: Operand::InlineNone,
-    }
+    Instruction::new(READONLY, Operand::InlineNone)
 }
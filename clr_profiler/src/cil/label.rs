@@ -0,0 +1,9 @@
+/// Symbolic handle for an instruction within a method body.
+///
+/// Branch operands and exception clause boundaries refer to labels instead of
+/// raw byte offsets, so instructions can be inserted or removed anywhere in a
+/// body. Labels are resolved back to relative offsets in `Method::into_bytes`.
+/// `Method::new` labels every branch target and clause boundary with its
+/// original byte offset, e.g. `Label(0x12)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Label(pub u32);
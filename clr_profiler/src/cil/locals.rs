@@ -0,0 +1,58 @@
+use crate::{
+    cil::{Error, Method, MethodHeader, Signature, Type},
+    MetadataEmitTrait, MetadataImportTrait,
+};
+
+/// Appends locals of the given types to a local variable signature blob,
+/// which is empty when there are no locals yet. Existing locals keep their
+/// indices.
+pub fn append_locals(signature: &[u8], locals: &[Type]) -> Result<Vec<u8>, Error> {
+    let mut types = local_types(signature)?;
+    types.extend_from_slice(locals);
+    // Local indices are u16 and 0xFFFF is reserved
+    if types.len() >= u16::MAX as usize {
+        return Err(Error::InvalidSignature);
+    }
+    Signature::LocalVar(types).to_bytes()
+}
+
+/// Types of the locals in a local variable signature.
+fn local_types(signature: &[u8]) -> Result<Vec<Type>, Error> {
+    if signature.is_empty() {
+        return Ok(Vec::new());
+    }
+    match Signature::from_bytes(signature)? {
+        Signature::LocalVar(types) => Ok(types),
+        _ => Err(Error::InvalidSignature),
+    }
+}
+
+impl Method {
+    /// Appends locals of the given types to the method. The current locals signature is read through `import`, and
+    /// the header is pointed at a token for the extended one obtained through
+    /// `emit`. Existing locals keep their indices so the IL is left untouched.
+    ///
+    /// Returns the index of the first added local. A tiny header is promoted,
+    /// and locals are zero initialized from then on as verifiable code requires.
+    pub fn add_locals<I, E>(&mut self, import: &I, emit: &E, locals: &[Type]) -> Result<u16, Error>
+    where
+        I: MetadataImportTrait,
+        E: MetadataEmitTrait,
+    {
+        let signature = match &self.method_header {
+            MethodHeader::Fat(header) if header.local_var_sig_tok != 0 => import
+                .get_sig_from_token(header.local_var_sig_tok)
+                .map_err(Error::Metadata)?,
+            _ => Vec::new(),
+        };
+        let first = local_types(&signature)?.len();
+        let signature = append_locals(&signature, locals)?;
+        let token = emit
+            .get_token_from_sig(&signature)
+            .map_err(Error::Metadata)?;
+        let header = self.method_header.expand();
+        header.local_var_sig_tok = token;
+        header.init_locals = true;
+        Ok(first as u16)
+    }
+}
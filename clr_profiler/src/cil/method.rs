@@ -1,9 +1,18 @@
 #![allow(non_upper_case_globals)]
-use crate::cil::{nearest_multiple, Error, Instruction, MethodHeader, Section};
+use crate::{
+    cil::{
+        flow, nearest_multiple, stack, widen_branches, ClauseLabels, Error, FatMethodHeader,
+        Instruction, Label, MethodHeader, Opcode, Operand, OperandParams, Section,
+        TinyMethodHeader,
+    },
+    ffi::COR_IL_MAP,
+};
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
+use std::ops::Range;
 use std::slice;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Method {
     pub method_header: MethodHeader,
     pub instructions: Vec<Instruction>,
@@ -12,104 +21,379 @@ pub struct Method {
 impl Method {
     pub fn new(method_header: *const u8, method_size: u32) -> Result<Self, Error> {
         let body = unsafe { slice::from_raw_parts(method_header, method_size as usize) };
-        let method_header = MethodHeader::from_bytes(&body)?;
-        let (instructions_start, instructions_end): (usize, usize) = match &method_header {
-            MethodHeader::Fat(header) => (12, (12 + header.code_size - 1) as usize),
+        Self::parse(body)
+    }
+    /// Parses a method body, header included. Malformed or truncated bodies
+    /// are reported as `Error::Malformed` with the offset where parsing failed,
+    /// this never panics.
+    pub fn parse(body: &[u8]) -> Result<Self, Error> {
+        let method_header = MethodHeader::from_bytes(body).map_err(|error| match error {
+            Error::InvalidMethodHeader => {
+                Error::Malformed(0, format!("unknown method header format 0x{:02X}", body[0]))
+            }
+            _ => Error::Malformed(0, "truncated method header".to_string()),
+        })?;
+        let (instructions_start, code_size): (usize, usize) = match &method_header {
+            MethodHeader::Fat(header) => {
+                (FatMethodHeader::SIZE as usize, header.code_size as usize)
+            }
             MethodHeader::Tiny(header) => (1, header.code_size as usize),
         };
-        let instruction_bytes = &body[instructions_start..=instructions_end];
-        let instructions = Self::instructions_from_bytes(instruction_bytes)?;
+        let instructions_end = instructions_start + code_size;
+        let instruction_bytes = body.get(instructions_start..instructions_end);
+        let instruction_bytes = instruction_bytes.ok_or_else(|| {
+            Error::Malformed(
+                instructions_start,
+                format!("code size {} runs past the end of the body", code_size),
+            )
+        })?;
+        let instructions = Self::instructions_from_bytes(instruction_bytes, instructions_start)?;
         let sections = match &method_header {
             MethodHeader::Fat(header) if header.more_sects => {
-                let sections_start = nearest_multiple(4, instructions_end + 1); // Sections must be DWORD aligned
-                let sections_bytes = &body[sections_start..];
-                Self::sections_from_bytes(sections_bytes)?
+                let sections_start = nearest_multiple(4, instructions_end); // Sections must be DWORD aligned
+                Self::sections_from_bytes(body, sections_start)?
             }
             _ => Vec::new(), // only fat headers with the more sections flag set have additional sections
         };
-        Ok(Method {
+        let mut method = Method {
             method_header,
             instructions,
             sections,
-        })
+        };
+        method.assign_labels()?;
+        Ok(method)
     }
-    pub fn into_bytes(&self) -> Vec<u8> {
+    /// Serializes the method, resolving every label to a relative offset
+    /// and recomputing the code size and exception clause offsets.
+    pub fn into_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut method = self.clone();
+        widen_branches(&mut method.instructions)?;
+        method.resolve_labels()?;
         let mut bytes = Vec::new();
-        bytes.append(&mut self.method_header.into_bytes());
-        bytes.append(&mut self.instructions_to_bytes());
-        bytes.append(&mut self.sections_to_bytes());
-        bytes
+        bytes.append(&mut method.method_header.into_bytes());
+        bytes.append(&mut method.instructions_to_bytes());
+        bytes.append(&mut method.sections_to_bytes(bytes.len()));
+        Ok(bytes)
+    }
+    /// Serializes the method, then checks the bytes parse back into a body
+    /// that serializes to the very same bytes. Meant for debug builds, to
+    /// catch a broken rewrite before the runtime is handed its IL.
+    ///
+    /// Returns the serialized bytes, or `Error::RoundTrip` with the offset of
+    /// the first byte that changed.
+    pub fn validate_roundtrip(&self) -> Result<Vec<u8>, Error> {
+        let bytes = self.into_bytes()?;
+        let reserialized = Self::parse(&bytes)?.into_bytes()?;
+        match bytes
+            .iter()
+            .zip(reserialized.iter())
+            .position(|(a, b)| a != b)
+        {
+            Some(offset) => Err(Error::RoundTrip(offset)),
+            None if bytes.len() != reserialized.len() => {
+                Err(Error::RoundTrip(bytes.len().min(reserialized.len())))
+            }
+            None => Ok(bytes),
+        }
     }
+    /// Raises the max stack, promoting a tiny header if it can't encode it.
+    pub fn set_max_stack(&mut self, max_stack: u16) {
+        match &mut self.method_header {
+            MethodHeader::Tiny(_) if max_stack <= FatMethodHeader::TINY_MAX_STACK => (),
+            method_header => method_header.expand().max_stack = max_stack,
+        }
+    }
+    /// Sets the locals signature, promoting a tiny header which has no locals.
+    pub fn set_local_var_sig_tok(&mut self, local_var_sig_tok: u32) {
+        self.method_header.expand().local_var_sig_tok = local_var_sig_tok;
+    }
+    /// Computes the deepest evaluation stack any path through the body reaches.
+    /// `signature` returns the signature blob of a call site token, it is
+    /// needed to tell how many arguments and return values calls have.
+    pub fn max_stack<F>(&self, signature: F) -> Result<u16, Error>
+    where
+        F: FnMut(u32) -> Option<Vec<u8>>,
+    {
+        let layout = flow::Layout::new(&self.instructions)?;
+        let mut entries = vec![(0, 0)];
+        for (label, depth) in self.handler_entries() {
+            entries.push((layout.position(label)?, depth));
+        }
+        let max_depth = stack::max_depth(&self.instructions, entries, signature)?;
+        u16::try_from(max_depth).or(Err(Error::InvalidCil))
+    }
+    /// Sets the header max stack to the one computed by `max_stack`.
+    pub fn update_max_stack<F>(&mut self, signature: F) -> Result<(), Error>
+    where
+        F: FnMut(u32) -> Option<Vec<u8>>,
+    {
+        let max_stack = self.max_stack(signature)?;
+        match &mut self.method_header {
+            MethodHeader::Fat(header) => header.max_stack = max_stack,
+            MethodHeader::Tiny(_) => self.set_max_stack(max_stack),
+        }
+        Ok(())
+    }
+    /// Inserts instructions at the start of the method, see `insert`.
     pub fn insert_prelude(&mut self, prelude: Vec<Instruction>) -> Result<(), Error> {
-        // For now ignore the operand stack. Assume we aren't exceeding the previous max stack size.
-        // Also assume we aren't adding any new exceptions or new method data sections.
-        // Also assume we aren't adding any local variables (I think this would require modifying the metadata)
-        // Also assume we don't need to expand any short branches, tiny headers, or small sections.
+        self.insert(0, prelude)
+    }
+    /// Inserts instructions before the instruction at `index`. Branches and
+    /// exception clauses that refer to that instruction keep referring to it,
+    /// so the inserted code is only reached by falling through into it.
+    ///
+    /// Labels defined inside `instructions` are local to them and renamed so
+    /// they can't collide with labels of the method. Labels that are only
+    /// referenced refer to instructions of the method. Branches with raw
+    /// relative offsets are allowed as long as they land inside `instructions`,
+    /// or directly after them.
+    ///
+    /// Returns `Error::OutOfRange` if `index > self.instructions.len()`.
+    pub fn insert(
+        &mut self,
+        index: usize,
+        mut instructions: Vec<Instruction>,
+    ) -> Result<(), Error> {
+        if index > self.instructions.len() {
+            return Err(Error::OutOfRange);
+        }
+        // Labels handed out below aren't in the method until the splice, so
+        // they all come from this counter rather than `new_label`.
+        let mut next_label = self.new_label().0;
+        let mut renamed = HashMap::new();
+        for instruction in instructions.iter_mut() {
+            if let Some(label) = instruction.label {
+                let local = Label(next_label);
+                next_label += 1;
+                if renamed.insert(label, local).is_some() {
+                    return Err(Error::DuplicateLabel(label));
+                }
+                instruction.label = Some(local);
+            }
+        }
+        for instruction in instructions.iter_mut() {
+            Self::rename_targets(&mut instruction.operand, &renamed);
+        }
 
-        let prelude_length: usize = prelude.iter().map(|i| i.into_bytes().len()).sum();
-        // update code_size in method_header
-        match &mut self.method_header {
-            MethodHeader::Fat(header) => {
-                let size = header.code_size as u128 + prelude_length as u128;
-                header.code_size = u32::try_from(size).or(Err(Error::PreludeTooBig))?;
+        let targets = Self::raw_targets(&instructions)?;
+        let offsets = Self::offsets_of(&instructions);
+        let end = Self::code_size_of(&instructions);
+        let mut labels = HashMap::new();
+        for target in targets.iter().flatten().flatten() {
+            if labels.contains_key(target) {
+                continue;
             }
-            MethodHeader::Tiny(header) => {
-                let size = header.code_size as u128 + prelude_length as u128;
-                header.code_size = u8::try_from(size)
-                    .or_else(|err| todo!("Expand into fat header!, {:?}", err))?;
+            let label = if *target == end && index < self.instructions.len() {
+                *self.instructions[index].label.get_or_insert_with(|| {
+                    next_label += 1;
+                    Label(next_label - 1)
+                })
+            } else {
+                let position = offsets
+                    .iter()
+                    .position(|offset| offset == target)
+                    .ok_or(Error::InvalidBranchTarget)?;
+                let instruction = &mut instructions[position];
+                *instruction.label.get_or_insert_with(|| {
+                    next_label += 1;
+                    Label(next_label - 1)
+                })
+            };
+            labels.insert(*target, label);
+        }
+        for (instruction, targets) in instructions.iter_mut().zip(targets) {
+            if let Some(targets) = targets {
+                let targets = targets.iter().map(|target| labels[target]).collect();
+                instruction.operand = Self::labelled_operand(&instruction.operand, targets);
             }
         }
-        // update try offset
-        // update handler offset
-        for section in &mut self.sections {
-            match section {
-                Section::FatSection(_, clauses) => {
-                    for clause in clauses {
-                        let try_offset = clause.try_offset as u128 + prelude_length as u128;
-                        clause.try_offset =
-                            u32::try_from(try_offset).or(Err(Error::PreludeTooBig))?;
-                        let handler_offset = clause.handler_offset as u128 + prelude_length as u128;
-                        clause.handler_offset =
-                            u32::try_from(handler_offset).or(Err(Error::PreludeTooBig))?;
-                    }
+
+        self.instructions.splice(index..index, instructions);
+        Ok(())
+    }
+    /// Removes the instructions in `range`. Branches and clause regions that
+    /// start at a removed instruction are moved to the instruction following
+    /// the range, regions that end at one are moved to the instruction before it.
+    ///
+    /// Returns `Error::OutOfRange` if `range` isn't within the instructions.
+    pub fn remove(&mut self, range: Range<usize>) -> Result<Vec<Instruction>, Error> {
+        if range.start > range.end || range.end > self.instructions.len() {
+            return Err(Error::OutOfRange);
+        }
+        let removed: HashSet<Label> = self.instructions[range.clone()]
+            .iter()
+            .filter_map(|i| i.label)
+            .collect();
+        if !removed.is_empty() {
+            let clause_labels = self.clause_labels();
+            let referenced_forward = self
+                .instructions
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !range.contains(index))
+                .flat_map(|(_, i)| i.operand.targets())
+                .chain(clause_labels.iter().flat_map(|labels| labels.starts()))
+                .any(|label| removed.contains(&label));
+            let referenced_backward = clause_labels
+                .iter()
+                .flat_map(|labels| labels.lasts())
+                .any(|label| removed.contains(&label));
+            let next = match (referenced_forward, range.end < self.instructions.len()) {
+                (false, _) => None,
+                (true, true) => Some(self.label_at(range.end)),
+                (true, false) => return Err(Error::InvalidBranchTarget),
+            };
+            let previous = match (referenced_backward, range.start > 0) {
+                (false, _) => None,
+                (true, true) => Some(self.label_at(range.start - 1)),
+                (true, false) => return Err(Error::InvalidExceptionClause),
+            };
+            let replace = |label: &mut Label, replacement: Option<Label>| {
+                if removed.contains(label) {
+                    *label = replacement.unwrap_or(*label);
                 }
-                Section::SmallSection(_, clauses) => {
-                    for clause in clauses {
-                        let try_offset = clause.try_offset as u128 + prelude_length as u128;
-                        clause.try_offset = u16::try_from(try_offset)
-                            .or_else(|err| todo!("Expand into fat section!, {:?}", err))?;
-                        let handler_offset = clause.handler_offset as u128 + prelude_length as u128;
-                        clause.handler_offset = u16::try_from(handler_offset)
-                            .or_else(|err| todo!("Expand into fat section!, {:?}", err))?;
+            };
+            if let Some(next) = next {
+                let forwarded = removed.iter().map(|label| (*label, next)).collect();
+                for (index, instruction) in self.instructions.iter_mut().enumerate() {
+                    if !range.contains(&index) {
+                        Self::rename_targets(&mut instruction.operand, &forwarded);
                     }
                 }
             }
+            for labels in self.clause_labels_mut() {
+                replace(&mut labels.try_start, next);
+                replace(&mut labels.handler_start, next);
+                if let Some(filter_start) = labels.filter_start.as_mut() {
+                    replace(filter_start, next);
+                }
+                replace(&mut labels.try_last, previous);
+                replace(&mut labels.handler_last, previous);
+            }
         }
-        // Insert the instructions
-        self.instructions.splice(0..0, prelude);
-        Ok(())
+        Ok(self.instructions.drain(range).collect())
+    }
+    /// Returns the label of the instruction at `index`, labelling it first if needed.
+    pub fn label_at(&mut self, index: usize) -> Label {
+        if let Some(label) = self.instructions[index].label {
+            return label;
+        }
+        let label = self.new_label();
+        self.instructions[index].label = Some(label);
+        label
+    }
+    /// Index of the instruction marked with `label`.
+    pub fn position(&self, label: Label) -> Option<usize> {
+        self.instructions
+            .iter()
+            .position(|i| i.label == Some(label))
+    }
+    /// Byte offset of every instruction, relative to the start of the code.
+    pub fn offsets(&self) -> Vec<usize> {
+        Self::offsets_of(&self.instructions)
     }
-    fn instructions_from_bytes(il: &[u8]) -> Result<Vec<Instruction>, Error> {
+    /// Maps the original offset of every instruction still in the body to its
+    /// offset once serialized, sorted by original offset. Passed to
+    /// `set_il_instrumented_code_map` so debuggers and stack traces keep
+    /// pointing at the original code.
+    pub fn il_map(&self) -> Result<Vec<COR_IL_MAP>, Error> {
+        let mut instructions = self.instructions.clone();
+        widen_branches(&mut instructions)?;
+        let mut map: Vec<_> = Self::offsets_of(&instructions)
+            .into_iter()
+            .zip(instructions.iter())
+            .filter_map(|(offset, instruction)| {
+                instruction.original_offset.map(|original| COR_IL_MAP {
+                    oldOffset: original,
+                    newOffset: offset as u32,
+                    fAccurate: 1,
+                })
+            })
+            .collect();
+        map.sort_by_key(|entry| entry.oldOffset);
+        Ok(map)
+    }
+    /// A label that isn't used anywhere in the method yet.
+    pub fn new_label(&self) -> Label {
+        let clause_labels = self.clause_labels();
+        self.instructions
+            .iter()
+            .flat_map(|i| i.label.into_iter().chain(i.operand.targets()))
+            .chain(
+                clause_labels
+                    .iter()
+                    .flat_map(|labels| labels.starts().into_iter().chain(labels.lasts())),
+            )
+            .max()
+            .map_or(Label(0), |label| Label(label.0 + 1))
+    }
+    /// Parses the code of a body, `start` is its offset for error messages.
+    fn instructions_from_bytes(il: &[u8], start: usize) -> Result<Vec<Instruction>, Error> {
         let mut index = 0;
         let mut instructions = Vec::new();
         while index < il.len() {
             let il = &il[index..];
-            let instruction = Instruction::from_bytes(il)?;
+            let mut instruction = Instruction::from_bytes(il).map_err(|error| {
+                let description = match (error, il) {
+                    (Error::InvalidCilOpcode, [0xFE, byte_2, ..]) => {
+                        format!("unknown opcode 0xFE 0x{:02X}", byte_2)
+                    }
+                    (Error::InvalidCilOpcode, [byte_1, ..]) => {
+                        format!("unknown opcode 0x{:02X}", byte_1)
+                    }
+                    (_, [0xFE]) => "truncated opcode".to_string(),
+                    (error, _) => {
+                        let opcode = match il {
+                            [0xFE, byte_2, ..] => Opcode::from_byte_pair((0xFE, *byte_2)),
+                            _ => Ok(Opcode::from_byte(il[0])),
+                        };
+                        let name = opcode.map_or("", |opcode| opcode.name);
+                        match error {
+                            Error::InvalidToken(token) => {
+                                format!("invalid token 0x{:08X} for `{}`", token, name)
+                            }
+                            _ => format!("truncated operand of `{}`", name),
+                        }
+                    }
+                };
+                Error::Malformed(start + index, description)
+            })?;
+            instruction.original_offset = Some(index as u32);
             index += instruction.length();
             instructions.push(instruction);
         }
         Ok(instructions)
     }
-    fn sections_from_bytes(il: &[u8]) -> Result<Vec<Section>, Error> {
-        let mut index = 0;
+    /// Parses the sections starting at `start` in `body`, following the more
+    /// sections flag of each section header.
+    fn sections_from_bytes(body: &[u8], start: usize) -> Result<Vec<Section>, Error> {
+        let mut index = start;
         let mut sections = Vec::new();
-        while index < il.len() {
-            let il = &il[index..];
-            let section = Section::from_bytes(il)?;
+        loop {
+            let number = sections.len();
+            let il = body
+                .get(index..)
+                .filter(|il| !il.is_empty())
+                .ok_or_else(|| Error::Malformed(index, format!("section {} is missing", number)))?;
+            let section = Section::from_bytes(il).map_err(|error| {
+                let description = match error {
+                    Error::InvalidSectionHeader => "runs past the end of the body",
+                    _ => "is truncated",
+                };
+                Error::Malformed(index, format!("section {} {}", number, description))
+            })?;
             index += section.data_size();
+            let more_sects = match &section {
+                Section::FatSection(header, _) => header.more_sects,
+                Section::SmallSection(header, _) => header.more_sects,
+            };
             sections.push(section);
+            if !more_sects {
+                return Ok(sections);
+            }
+            // Sections following another one are DWORD aligned as well
+            index = nearest_multiple(4, index);
         }
-        Ok(sections)
     }
     fn instructions_to_bytes(&self) -> Vec<u8> {
         self.instructions
@@ -117,15 +401,13 @@ impl Method {
             .flat_map(|i| i.into_bytes())
             .collect()
     }
-    fn sections_to_bytes(&self) -> Vec<u8> {
+    /// Sections follow the code, `code_end` is the length of the header and code.
+    fn sections_to_bytes(&self, code_end: usize) -> Vec<u8> {
         let mut bytes = Vec::new();
         match &self.method_header {
             MethodHeader::Fat(header) if header.more_sects => {
-                // Sections must be DWORD aligned. Add zero padding at the end to achieve alignment.
-                let padding_byte_size = 4 - bytes.len() % 4;
-                for _ in 0..padding_byte_size {
-                    bytes.push(0);
-                }
+                // Sections must be DWORD aligned. Add zero padding after the code to achieve alignment.
+                bytes.resize(nearest_multiple(4, code_end) - code_end, 0);
                 let mut section_bytes = self.sections.iter().flat_map(|s| s.into_bytes()).collect();
                 bytes.append(&mut section_bytes);
             }
@@ -133,4 +415,347 @@ impl Method {
         }
         bytes
     }
+    fn offsets_of(instructions: &[Instruction]) -> Vec<usize> {
+        instructions
+            .iter()
+            .scan(0, |offset, i| {
+                let start = *offset;
+                *offset += i.length();
+                Some(start)
+            })
+            .collect()
+    }
+    fn code_size_of(instructions: &[Instruction]) -> usize {
+        instructions.iter().map(|i| i.length()).sum()
+    }
+    /// Absolute target offsets of every branch that still uses raw relative offsets.
+    fn raw_targets(instructions: &[Instruction]) -> Result<Vec<Option<Vec<usize>>>, Error> {
+        let offsets = Self::offsets_of(instructions);
+        let absolute = |index: usize, delta: i64| {
+            let next = (offsets[index] + instructions[index].length()) as i64;
+            usize::try_from(next + delta).or(Err(Error::InvalidBranchTarget))
+        };
+        instructions
+            .iter()
+            .enumerate()
+            .map(|(index, instruction)| match &instruction.operand {
+                Operand::ShortInlineBrTarget(delta) => {
+                    Ok(Some(vec![absolute(index, *delta as i64)?]))
+                }
+                Operand::InlineBrTarget(delta) => Ok(Some(vec![absolute(index, *delta as i64)?])),
+                Operand::InlineSwitch(_, deltas) => deltas
+                    .iter()
+                    .map(|delta| absolute(index, *delta as i64))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Some),
+                _ => Ok(None),
+            })
+            .collect()
+    }
+    fn labelled_operand(operand: &Operand, targets: Vec<Label>) -> Operand {
+        match operand {
+            Operand::InlineSwitch(_, _) => Operand::SwitchTargets(targets),
+            _ => Operand::BrTarget(targets[0]),
+        }
+    }
+    pub(crate) fn rename_targets(operand: &mut Operand, renamed: &HashMap<Label, Label>) {
+        match operand {
+            Operand::BrTarget(target) => {
+                *target = *renamed.get(target).unwrap_or(target);
+            }
+            Operand::SwitchTargets(targets) => {
+                for target in targets.iter_mut() {
+                    *target = *renamed.get(target).unwrap_or(target);
+                }
+            }
+            _ => (),
+        }
+    }
+    fn clause_labels(&self) -> Vec<ClauseLabels> {
+        self.sections
+            .iter()
+            .flat_map(|section| match section {
+                Section::FatSection(_, clauses) => {
+                    clauses.iter().filter_map(|c| c.labels).collect::<Vec<_>>()
+                }
+                Section::SmallSection(_, clauses) => {
+                    clauses.iter().filter_map(|c| c.labels).collect::<Vec<_>>()
+                }
+            })
+            .collect()
+    }
+    /// Handler and filter entry points with the stack depth they start with:
+    /// catch handlers and filters get the exception object pushed.
+    fn handler_entries(&self) -> Vec<(Label, usize)> {
+        let mut entries = Vec::new();
+        let mut add = |labels: &ClauseLabels, is_finally_or_fault: bool| {
+            entries.push((labels.handler_start, !is_finally_or_fault as usize));
+            entries.extend(labels.filter_start.map(|label| (label, 1)));
+        };
+        for section in self.sections.iter() {
+            match section {
+                Section::FatSection(_, clauses) => clauses.iter().for_each(|c| {
+                    c.labels
+                        .iter()
+                        .for_each(|l| add(l, c.is_finally || c.is_fault))
+                }),
+                Section::SmallSection(_, clauses) => clauses.iter().for_each(|c| {
+                    c.labels
+                        .iter()
+                        .for_each(|l| add(l, c.is_finally || c.is_fault))
+                }),
+            }
+        }
+        entries
+    }
+    pub(crate) fn clause_labels_mut(&mut self) -> Vec<&mut ClauseLabels> {
+        self.sections
+            .iter_mut()
+            .flat_map(|section| match section {
+                Section::FatSection(_, clauses) => clauses
+                    .iter_mut()
+                    .filter_map(|c| c.labels.as_mut())
+                    .collect::<Vec<_>>(),
+                Section::SmallSection(_, clauses) => clauses
+                    .iter_mut()
+                    .filter_map(|c| c.labels.as_mut())
+                    .collect::<Vec<_>>(),
+            })
+            .collect()
+    }
+    /// Replaces the raw offsets of a freshly parsed body with labels. Each
+    /// label is named after the original offset of the instruction it marks.
+    fn assign_labels(&mut self) -> Result<(), Error> {
+        let offsets = self.offsets();
+        let code_size = Self::code_size_of(&self.instructions);
+        let positions: HashMap<usize, usize> = offsets
+            .iter()
+            .enumerate()
+            .map(|(index, offset)| (*offset, index))
+            .collect();
+        let start = |offset: u32| -> Result<Label, Error> {
+            match positions.contains_key(&(offset as usize)) {
+                true => Ok(Label(offset)),
+                false => Err(Error::InvalidExceptionClause),
+            }
+        };
+        let last = |offset: u32, length: u32| -> Result<Label, Error> {
+            let end = offset as usize + length as usize;
+            let index = match positions.get(&end) {
+                Some(index) if *index > 0 => index - 1,
+                None if end == code_size && !offsets.is_empty() => offsets.len() - 1,
+                _ => return Err(Error::InvalidExceptionClause),
+            };
+            Ok(Label(offsets[index] as u32))
+        };
+        let clause = |try_offset: u32,
+                      try_length: u32,
+                      handler_offset: u32,
+                      handler_length: u32,
+                      filter_offset: Option<u32>|
+         -> Result<ClauseLabels, Error> {
+            Ok(ClauseLabels {
+                try_start: start(try_offset)?,
+                try_last: last(try_offset, try_length)?,
+                handler_start: start(handler_offset)?,
+                handler_last: last(handler_offset, handler_length)?,
+                filter_start: filter_offset.map(start).transpose()?,
+            })
+        };
+        for section in self.sections.iter_mut() {
+            match section {
+                Section::FatSection(_, clauses) => {
+                    for c in clauses.iter_mut() {
+                        let filter = Some(c.class_token_or_filter_offset).filter(|_| c.is_filter);
+                        c.labels = Some(clause(
+                            c.try_offset,
+                            c.try_length,
+                            c.handler_offset,
+                            c.handler_length,
+                            filter,
+                        )?);
+                    }
+                }
+                Section::SmallSection(_, clauses) => {
+                    for c in clauses.iter_mut() {
+                        let filter = Some(c.class_token_or_filter_offset).filter(|_| c.is_filter);
+                        c.labels = Some(clause(
+                            c.try_offset as u32,
+                            c.try_length as u32,
+                            c.handler_offset as u32,
+                            c.handler_length as u32,
+                            filter,
+                        )?);
+                    }
+                }
+            }
+        }
+
+        let targets = Self::raw_targets(&self.instructions)?;
+        let mut marked: Vec<usize> = targets.iter().flatten().flatten().copied().collect();
+        for labels in self.clause_labels() {
+            let clause_labels = labels.starts().into_iter().chain(labels.lasts());
+            marked.extend(clause_labels.map(|label| label.0 as usize));
+        }
+        for offset in marked {
+            let index = *positions.get(&offset).ok_or(Error::InvalidBranchTarget)?;
+            self.instructions[index].label = Some(Label(offset as u32));
+        }
+        for (instruction, targets) in self.instructions.iter_mut().zip(targets) {
+            if let Some(targets) = targets {
+                let targets = targets.iter().map(|offset| Label(*offset as u32)).collect();
+                instruction.operand = Self::labelled_operand(&instruction.operand, targets);
+            }
+        }
+        Ok(())
+    }
+    /// Turns every label back into relative offsets for the current layout.
+    fn resolve_labels(&mut self) -> Result<(), Error> {
+        let offsets = self.offsets();
+        let code_size = Self::code_size_of(&self.instructions);
+        let mut positions = HashMap::new();
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            if let Some(label) = instruction.label {
+                if positions.insert(label, index).is_some() {
+                    return Err(Error::DuplicateLabel(label));
+                }
+            }
+        }
+        let target = |label: Label| -> Result<usize, Error> {
+            positions
+                .get(&label)
+                .map(|index| offsets[*index])
+                .ok_or(Error::UndefinedLabel(label))
+        };
+        for (index, instruction) in self.instructions.iter_mut().enumerate() {
+            let next = (offsets[index] + instruction.length()) as i64;
+            let delta = |label: Label| -> Result<i64, Error> { Ok(target(label)? as i64 - next) };
+            let operand = match (&instruction.operand, &instruction.opcode.operand_params) {
+                (Operand::BrTarget(label), OperandParams::ShortInlineBrTarget) => {
+                    let delta =
+                        i8::try_from(delta(*label)?).or(Err(Error::BranchOutOfRange(*label)))?;
+                    Operand::ShortInlineBrTarget(delta)
+                }
+                (Operand::BrTarget(label), _) => {
+                    let delta =
+                        i32::try_from(delta(*label)?).or(Err(Error::BranchOutOfRange(*label)))?;
+                    Operand::InlineBrTarget(delta)
+                }
+                (Operand::SwitchTargets(labels), _) => {
+                    let deltas = labels
+                        .iter()
+                        .map(|label| {
+                            i32::try_from(delta(*label)?).or(Err(Error::BranchOutOfRange(*label)))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Operand::InlineSwitch(deltas.len() as u32, deltas)
+                }
+                _ => continue,
+            };
+            instruction.operand = operand;
+        }
+
+        let more_sects = !self.sections.is_empty();
+        match &mut self.method_header {
+            MethodHeader::Tiny(header)
+                if !more_sects && code_size <= TinyMethodHeader::MAX_CODE_SIZE as usize =>
+            {
+                header.code_size = code_size as u8;
+            }
+            method_header => {
+                let header = method_header.expand();
+                header.code_size = u32::try_from(code_size).or(Err(Error::PreludeTooBig))?;
+                header.more_sects = more_sects;
+            }
+        }
+
+        let end = |label: Label| -> Result<usize, Error> {
+            let index = positions.get(&label).ok_or(Error::UndefinedLabel(label))?;
+            Ok(offsets[*index] + self.instructions[*index].length())
+        };
+        let region = |start: Label, last: Label| -> Result<(u32, u32), Error> {
+            let offset = target(start)?;
+            let length = end(last)?
+                .checked_sub(offset)
+                .filter(|length| *length > 0)
+                .ok_or(Error::InvalidExceptionClause)?;
+            Ok((offset as u32, length as u32))
+        };
+        let mut resolved = Vec::new();
+        for labels in self.clause_labels() {
+            let (try_offset, try_length) = region(labels.try_start, labels.try_last)?;
+            let (handler_offset, handler_length) =
+                region(labels.handler_start, labels.handler_last)?;
+            let filter_offset = labels.filter_start.map(target).transpose()?;
+            resolved.push(ClauseRegions {
+                try_offset,
+                try_length,
+                handler_offset,
+                handler_length,
+                filter_offset: filter_offset.map(|offset| offset as u32),
+            });
+        }
+        let mut resolved = resolved.into_iter();
+        let sections_count = self.sections.len();
+        for (index, section) in self.sections.iter_mut().enumerate() {
+            let labelled = match section {
+                Section::FatSection(_, clauses) => {
+                    clauses.iter().filter(|c| c.labels.is_some()).count()
+                }
+                Section::SmallSection(_, clauses) => {
+                    clauses.iter().filter(|c| c.labels.is_some()).count()
+                }
+            };
+            let regions: Vec<_> = resolved.by_ref().take(labelled).collect();
+            if !regions.iter().all(ClauseRegions::fits_small) {
+                section.expand();
+            }
+            let mut regions = regions.into_iter();
+            match section {
+                Section::FatSection(_, clauses) => {
+                    for c in clauses.iter_mut().filter(|c| c.labels.is_some()) {
+                        let regions = regions.next().unwrap();
+                        c.try_offset = regions.try_offset;
+                        c.try_length = regions.try_length;
+                        c.handler_offset = regions.handler_offset;
+                        c.handler_length = regions.handler_length;
+                        if let Some(filter_offset) = regions.filter_offset {
+                            c.class_token_or_filter_offset = filter_offset;
+                        }
+                    }
+                }
+                Section::SmallSection(_, clauses) => {
+                    // Every region fits, otherwise the section was expanded above
+                    for c in clauses.iter_mut().filter(|c| c.labels.is_some()) {
+                        let regions = regions.next().unwrap();
+                        c.try_offset = regions.try_offset as u16;
+                        c.try_length = regions.try_length as u8;
+                        c.handler_offset = regions.handler_offset as u16;
+                        c.handler_length = regions.handler_length as u8;
+                        if let Some(filter_offset) = regions.filter_offset {
+                            c.class_token_or_filter_offset = filter_offset;
+                        }
+                    }
+                }
+            }
+            section.update_header(index + 1 < sections_count);
+        }
+        Ok(())
+    }
+}
+/// Offsets and lengths of an exception handling clause resolved from its labels.
+struct ClauseRegions {
+    try_offset: u32,
+    try_length: u32,
+    handler_offset: u32,
+    handler_length: u32,
+    filter_offset: Option<u32>,
+}
+impl ClauseRegions {
+    fn fits_small(&self) -> bool {
+        u16::try_from(self.try_offset).is_ok()
+            && u8::try_from(self.try_length).is_ok()
+            && u16::try_from(self.handler_offset).is_ok()
+            && u8::try_from(self.handler_length).is_ok()
+    }
 }
@@ -1,5 +1,5 @@
 #![allow(non_upper_case_globals)]
-use crate::cil::{check_flag, il_u32, Error};
+use crate::cil::{check_flag, il_u16, il_u32, il_u8, Error};
 
 bitflags! {
     pub struct MethodHeaderFlags: u8 {
@@ -9,7 +9,7 @@ bitflags! {
         const CorILMethod_InitLocals = 0x10;
     }
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FatMethodHeader {
     pub more_sects: bool,
     pub init_locals: bool,
@@ -19,28 +19,45 @@ pub struct FatMethodHeader {
 }
 impl FatMethodHeader {
     pub const SIZE: u8 = 12;
+    /// Max stack the runtime assumes for methods with a tiny header.
+    pub const TINY_MAX_STACK: u16 = 8;
 }
-#[derive(Debug)]
+impl From<&TinyMethodHeader> for FatMethodHeader {
+    fn from(header: &TinyMethodHeader) -> Self {
+        FatMethodHeader {
+            more_sects: false,
+            init_locals: false,
+            max_stack: Self::TINY_MAX_STACK,
+            code_size: header.code_size as u32,
+            local_var_sig_tok: 0,
+        }
+    }
+}
+#[derive(Debug, Clone, PartialEq)]
 pub struct TinyMethodHeader {
     pub code_size: u8,
 }
-#[derive(Debug)]
+impl TinyMethodHeader {
+    /// The tiny header encodes the code size in 6 bits
+    pub const MAX_CODE_SIZE: u8 = 63;
+}
+#[derive(Debug, Clone, PartialEq)]
 pub enum MethodHeader {
     Fat(FatMethodHeader),
     Tiny(TinyMethodHeader),
 }
 impl MethodHeader {
     pub fn from_bytes(method_il: &[u8]) -> Result<Self, Error> {
-        let header_flags = method_il[0];
+        let header_flags = il_u8(method_il, 0)?;
         if Self::is_tiny(header_flags) {
             // In a tiny header, the first 6 bits encode the code size
-            let code_size = method_il[0] >> 2;
+            let code_size = header_flags >> 2;
             let tiny_header = TinyMethodHeader { code_size };
             Ok(MethodHeader::Tiny(tiny_header))
         } else if Self::is_fat(header_flags) {
             let more_sects = Self::more_sects(header_flags);
             let init_locals = Self::init_locals(header_flags);
-            let max_stack = u16::from_le_bytes([method_il[2], method_il[3]]);
+            let max_stack = il_u16(method_il, 2)?;
             let code_size = il_u32(method_il, 4)?;
             let local_var_sig_tok = il_u32(method_il, 8)?;
             let fat_header = FatMethodHeader {
@@ -55,6 +72,22 @@ impl MethodHeader {
             Err(Error::InvalidMethodHeader)
         }
     }
+    /// Promotes a tiny header into an equivalent fat one and returns it.
+    pub fn expand(&mut self) -> &mut FatMethodHeader {
+        if let MethodHeader::Tiny(header) = self {
+            *self = MethodHeader::Fat(FatMethodHeader::from(&*header));
+        }
+        match self {
+            MethodHeader::Fat(header) => header,
+            MethodHeader::Tiny(_) => unreachable!(),
+        }
+    }
+    pub fn max_stack(&self) -> u16 {
+        match self {
+            MethodHeader::Fat(header) => header.max_stack,
+            MethodHeader::Tiny(_) => FatMethodHeader::TINY_MAX_STACK,
+        }
+    }
     pub fn into_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
         match &self {
@@ -0,0 +1,362 @@
+use crate::{
+    cil::{Error, MethodSignature, Token, TokenTable, Type},
+    ffi::{mdMethodDef, mdToken, CorElementType},
+    MetadataImportTrait,
+};
+
+/// How type names are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameStyle {
+    /// Full names with nested types after a `+` and primitives by their ILAsm
+    /// keyword, e.g. `System.Collections.Generic.Dictionary<string, int32>`.
+    ILAsm,
+    /// Names without namespaces with nested types after a `.` and built-in
+    /// types by their keyword, e.g. `Dictionary<string, int>`.
+    CSharp,
+}
+impl NameStyle {
+    pub fn primitive_name(&self, element_type: CorElementType) -> Option<&'static str> {
+        match self {
+            NameStyle::ILAsm => primitive_name(element_type),
+            NameStyle::CSharp => csharp_primitive_name(element_type),
+        }
+    }
+    /// Name of a type given its full name as `type_name` writes it and the
+    /// names of its type arguments, see `ilasm_name` and `csharp_name`.
+    pub fn class_name(&self, full_name: &str, type_args: &[String]) -> String {
+        match self {
+            NameStyle::ILAsm => ilasm_name(full_name, type_args),
+            NameStyle::CSharp => csharp_name(full_name, type_args),
+        }
+    }
+}
+
+/// Names a method after its type, generic arguments and signature, e.g.
+/// `Namespace.Outer`1+Inner.Method<T>(int32, string) : void`. Types are named
+/// in the `NameStyle::ILAsm` style.
+///
+/// `class_type_args` and `method_type_args` name the type arguments of an
+/// instantiation, of the type and of the method, such as the ones
+/// `get_class_id_info_2` and `get_function_info_2` return. They are written as
+/// given, so they should be named in the same style, e.g. by a
+/// `ClassNameResolver` using `NameStyle::ILAsm`. When empty, the generic
+/// parameters are named as declared, e.g. ``List`1.Add`` becomes
+/// `List<T>.Add`.
+pub fn method_name<T: MetadataImportTrait>(
+    metadata: &T,
+    method: mdMethodDef,
+    class_type_args: &[String],
+    method_type_args: &[String],
+) -> Result<String, Error> {
+    let props = metadata.get_method_props(method).map_err(Error::Metadata)?;
+    if props.sig.is_null() {
+        return Err(Error::InvalidSignature);
+    }
+    // The blob is owned by the metadata, which outlives the props
+    let sig = unsafe { std::slice::from_raw_parts(props.sig, props.sig_length as usize) };
+    let signature = MethodSignature::from_bytes(sig)?;
+    let names = Names {
+        metadata,
+        class_params: match class_type_args.is_empty() {
+            true => generic_param_names(metadata, props.class_token)?,
+            false => class_type_args.to_vec(),
+        },
+        method_params: match method_type_args.is_empty() {
+            true => generic_param_names(metadata, method)?,
+            false => method_type_args.to_vec(),
+        },
+    };
+
+    let class_name = ilasm_name(
+        &type_name(metadata, props.class_token)?,
+        &names.class_params,
+    );
+    let mut name = format!("{}.{}", class_name, props.name);
+    if signature.generic_param_count > 0 {
+        let params: Vec<_> = (0..signature.generic_param_count)
+            .map(|index| names.method_param(index))
+            .collect();
+        name.push_str(&format!("<{}>", params.join(", ")));
+    }
+    let mut params = Vec::new();
+    for (index, param) in signature.params.iter().enumerate() {
+        if signature.sentinel == Some(index) {
+            params.push("...".to_string());
+        }
+        params.push(names.type_name(param)?);
+    }
+    let return_type = names.type_name(&signature.return_type)?;
+    Ok(format!("{}({}) : {}", name, params.join(", "), return_type))
+}
+
+/// Full name of a type definition or reference, with nested types following
+/// their enclosing type after a `+`, e.g. `Namespace.Outer+Inner`. Other
+/// tokens are written as `token(0x...)`.
+pub fn type_name<T: MetadataImportTrait>(metadata: &T, token: mdToken) -> Result<String, Error> {
+    match Token::from_raw(token).map(|token| token.table()) {
+        Ok(TokenTable::TypeDef) => {
+            let props = metadata.get_typedef_props(token).map_err(Error::Metadata)?;
+            // Types which aren't nested have no enclosing class to look up
+            match metadata.get_nested_class_props(token) {
+                Ok(enclosing) if enclosing != 0 && enclosing != token => Ok(format!(
+                    "{}+{}",
+                    type_name(metadata, enclosing)?,
+                    props.name
+                )),
+                _ => Ok(props.name),
+            }
+        }
+        Ok(TokenTable::TypeRef) => {
+            let props = metadata.get_typeref_props(token).map_err(Error::Metadata)?;
+            match Token::from_raw(props.resolution_scope).map(|scope| scope.table()) {
+                Ok(TokenTable::TypeRef) if props.resolution_scope != token => Ok(format!(
+                    "{}+{}",
+                    type_name(metadata, props.resolution_scope)?,
+                    props.name
+                )),
+                _ => Ok(props.name),
+            }
+        }
+        _ => Ok(format!("token(0x{:08X})", token)),
+    }
+}
+
+/// Name of a type decoded from a signature, e.g. from a TypeSpec blob, such
+/// as `System.Collections.Generic.List<int32>`. Generic parameters are named by
+/// index, `!0` for the ones of the type and `!!0` for the ones of the method.
+pub fn signature_type_name<T: MetadataImportTrait>(
+    metadata: &T,
+    type_: &Type,
+) -> Result<String, Error> {
+    let names = Names {
+        metadata,
+        class_params: Vec::new(),
+        method_params: Vec::new(),
+    };
+    names.type_name(type_)
+}
+
+/// ILAsm name of a primitive type, such as `int32`.
+pub fn primitive_name(element_type: CorElementType) -> Option<&'static str> {
+    use CorElementType::*;
+    let name = match element_type {
+        ELEMENT_TYPE_VOID => "void",
+        ELEMENT_TYPE_BOOLEAN => "bool",
+        ELEMENT_TYPE_CHAR => "char",
+        ELEMENT_TYPE_I1 => "int8",
+        ELEMENT_TYPE_U1 => "uint8",
+        ELEMENT_TYPE_I2 => "int16",
+        ELEMENT_TYPE_U2 => "uint16",
+        ELEMENT_TYPE_I4 => "int32",
+        ELEMENT_TYPE_U4 => "uint32",
+        ELEMENT_TYPE_I8 => "int64",
+        ELEMENT_TYPE_U8 => "uint64",
+        ELEMENT_TYPE_R4 => "float32",
+        ELEMENT_TYPE_R8 => "float64",
+        ELEMENT_TYPE_STRING => "string",
+        ELEMENT_TYPE_TYPEDBYREF => "typedref",
+        ELEMENT_TYPE_I => "native int",
+        ELEMENT_TYPE_U => "native uint",
+        ELEMENT_TYPE_OBJECT => "object",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// ILAsm name of a type, given its full name as `type_name` writes it and the
+/// names of its type arguments, e.g. ``System.Collections.Generic.List`1``
+/// with `int32` becomes `System.Collections.Generic.List<int32>`. Built-in
+/// types are named with their keyword, such as `int32` for `System.Int32`.
+pub fn ilasm_name(full_name: &str, type_args: &[String]) -> String {
+    match builtin_element_type(full_name).and_then(primitive_name) {
+        Some(keyword) if type_args.is_empty() => keyword.to_string(),
+        _ => generic_instance_name(full_name, type_args),
+    }
+}
+
+/// C# name of a type, given its full name as `type_name` writes it and the
+/// names of its type arguments, e.g. ``System.Collections.Generic.List`1``
+/// with `int` becomes `List<int>`. Namespaces are left out, nested types
+/// follow their enclosing type after a `.`, and built-in types are named with
+/// their keyword.
+///
+/// Nested types of generic types take the type arguments of their enclosing
+/// types first, so ``Outer`1+Inner`1`` with `int` and `string` becomes
+/// `Outer<int>.Inner<string>`.
+pub fn csharp_name(full_name: &str, type_args: &[String]) -> String {
+    if type_args.is_empty() {
+        if let Some(keyword) = csharp_keyword(full_name) {
+            return keyword.to_string();
+        }
+    }
+    let mut type_args = type_args.iter();
+    full_name
+        .split('+')
+        .enumerate()
+        .map(|(index, name)| {
+            // Only the outermost type has a namespace
+            let name = match index {
+                0 => name.rsplit('.').next().unwrap_or(name),
+                _ => name,
+            };
+            let (name, arity) = match name.rfind('`') {
+                Some(tick) => match name[tick + 1..].parse() {
+                    Ok(arity) => (&name[..tick], arity),
+                    Err(_) => (name, 0),
+                },
+                None => (name, 0),
+            };
+            let args: Vec<_> = type_args.by_ref().take(arity).cloned().collect();
+            match args.is_empty() {
+                true => name.to_string(),
+                false => format!("{}<{}>", name, args.join(", ")),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// C# keyword of a built-in type, such as `int`.
+pub fn csharp_primitive_name(element_type: CorElementType) -> Option<&'static str> {
+    use CorElementType::*;
+    let name = match element_type {
+        ELEMENT_TYPE_VOID => "void",
+        ELEMENT_TYPE_BOOLEAN => "bool",
+        ELEMENT_TYPE_CHAR => "char",
+        ELEMENT_TYPE_I1 => "sbyte",
+        ELEMENT_TYPE_U1 => "byte",
+        ELEMENT_TYPE_I2 => "short",
+        ELEMENT_TYPE_U2 => "ushort",
+        ELEMENT_TYPE_I4 => "int",
+        ELEMENT_TYPE_U4 => "uint",
+        ELEMENT_TYPE_I8 => "long",
+        ELEMENT_TYPE_U8 => "ulong",
+        ELEMENT_TYPE_R4 => "float",
+        ELEMENT_TYPE_R8 => "double",
+        ELEMENT_TYPE_STRING => "string",
+        ELEMENT_TYPE_I => "nint",
+        ELEMENT_TYPE_U => "nuint",
+        ELEMENT_TYPE_OBJECT => "object",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn csharp_keyword(full_name: &str) -> Option<&'static str> {
+    match full_name {
+        // Not a primitive of the runtime, but of the language
+        "System.Decimal" => Some("decimal"),
+        _ => builtin_element_type(full_name).and_then(csharp_primitive_name),
+    }
+}
+
+/// Element type of a type of the core library which signatures encode as a
+/// primitive, e.g. `ELEMENT_TYPE_I4` for `System.Int32`.
+fn builtin_element_type(full_name: &str) -> Option<CorElementType> {
+    use CorElementType::*;
+    let element_type = match full_name {
+        "System.Void" => ELEMENT_TYPE_VOID,
+        "System.Boolean" => ELEMENT_TYPE_BOOLEAN,
+        "System.Char" => ELEMENT_TYPE_CHAR,
+        "System.SByte" => ELEMENT_TYPE_I1,
+        "System.Byte" => ELEMENT_TYPE_U1,
+        "System.Int16" => ELEMENT_TYPE_I2,
+        "System.UInt16" => ELEMENT_TYPE_U2,
+        "System.Int32" => ELEMENT_TYPE_I4,
+        "System.UInt32" => ELEMENT_TYPE_U4,
+        "System.Int64" => ELEMENT_TYPE_I8,
+        "System.UInt64" => ELEMENT_TYPE_U8,
+        "System.Single" => ELEMENT_TYPE_R4,
+        "System.Double" => ELEMENT_TYPE_R8,
+        "System.String" => ELEMENT_TYPE_STRING,
+        "System.TypedReference" => ELEMENT_TYPE_TYPEDBYREF,
+        "System.IntPtr" => ELEMENT_TYPE_I,
+        "System.UIntPtr" => ELEMENT_TYPE_U,
+        "System.Object" => ELEMENT_TYPE_OBJECT,
+        _ => return None,
+    };
+    Some(element_type)
+}
+
+/// Name of a generic type followed by its type arguments, without the arity
+/// the name ends with, e.g. ``List`1`` with `int32` becomes `List<int32>`.
+fn generic_instance_name(name: &str, type_args: &[String]) -> String {
+    if type_args.is_empty() {
+        return name.to_string();
+    }
+    let name = match name.rfind('`') {
+        Some(index) if name[index + 1..].bytes().all(|b| b.is_ascii_digit()) => &name[..index],
+        _ => name,
+    };
+    format!("{}<{}>", name, type_args.join(", "))
+}
+
+/// Names of the generic parameters of a type or method definition, in order.
+fn generic_param_names<T: MetadataImportTrait>(
+    metadata: &T,
+    owner: mdToken,
+) -> Result<Vec<String>, Error> {
+    let mut params = metadata
+        .enum_generic_params(owner)
+        .map_err(Error::Metadata)?
+        .into_iter()
+        .map(|param| metadata.get_generic_param_props(param))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::Metadata)?;
+    params.sort_by_key(|props| props.seq);
+    Ok(params.into_iter().map(|props| props.name).collect())
+}
+
+struct Names<'a, T: MetadataImportTrait> {
+    metadata: &'a T,
+    class_params: Vec<String>,
+    method_params: Vec<String>,
+}
+impl<'a, T: MetadataImportTrait> Names<'a, T> {
+    fn method_param(&self, index: u32) -> String {
+        match self.method_params.get(index as usize) {
+            Some(name) => name.clone(),
+            None => format!("!!{}", index),
+        }
+    }
+    fn type_name(&self, type_: &Type) -> Result<String, Error> {
+        let name = match type_ {
+            Type::Primitive(element_type) => primitive_name(*element_type)
+                .ok_or(Error::InvalidSignature)?
+                .to_string(),
+            Type::Class(token) | Type::ValueType(token) => type_name(self.metadata, token.raw())?,
+            Type::GenericInst { generic, args, .. } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.type_name(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                generic_instance_name(&type_name(self.metadata, generic.raw())?, &args)
+            }
+            Type::Ptr(type_) => format!("{}*", self.type_name(type_)?),
+            Type::ByRef(type_) => format!("{}&", self.type_name(type_)?),
+            Type::SzArray(type_) => format!("{}[]", self.type_name(type_)?),
+            Type::Array(type_, shape) => {
+                let commas = ",".repeat(shape.rank.saturating_sub(1) as usize);
+                format!("{}[{}]", self.type_name(type_)?, commas)
+            }
+            Type::Var(index) => match self.class_params.get(*index as usize) {
+                Some(name) => name.clone(),
+                None => format!("!{}", index),
+            },
+            Type::MVar(index) => self.method_param(*index),
+            Type::FnPtr(signature) => {
+                let params = signature
+                    .params
+                    .iter()
+                    .map(|param| self.type_name(param))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let return_type = self.type_name(&signature.return_type)?;
+                format!("method {} *({})", return_type, params.join(", "))
+            }
+            // Modifiers such as `modreq(IsVolatile)` don't tell overloads apart
+            Type::Modified { modified, .. } => self.type_name(modified)?,
+            Type::Pinned(type_) => format!("{} pinned", self.type_name(type_)?),
+        };
+        Ok(name)
+    }
+}
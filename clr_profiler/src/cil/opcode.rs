@@ -1,6 +1,6 @@
 use crate::cil::Error;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StackBehaviorPop {
     Pop0,
     Pop1,
@@ -23,7 +23,7 @@ pub enum StackBehaviorPop {
     PopRefPopIPop1,
     PopIPopIPopI,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StackBehaviorPush {
     Push0,
     Push1,
@@ -35,7 +35,7 @@ pub enum StackBehaviorPush {
     Push1Push1,
     VarPush,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OperandParams {
     InlineNone,
     ShortInlineVar,
@@ -55,7 +55,7 @@ pub enum OperandParams {
     InlineField,
     InlineTok,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OpcodeKind {
     Primitive,
     Macro,
@@ -63,7 +63,7 @@ pub enum OpcodeKind {
     Internal,
     Prefix,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ControlFlow {
     Next,
     Break,
@@ -74,7 +74,7 @@ pub enum ControlFlow {
     Throw,
     Meta,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Opcode {
     pub name: &'static str,
     pub stack_behavior_pop: StackBehaviorPop,
@@ -111,6 +111,31 @@ impl Opcode {
             control_flow,
         }
     }
+    /// Opcode with the given mnemonic, e.g. `ldc.i4.s`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let one_byte = (0x00..=0xFF).map(Self::from_byte);
+        let two_bytes = (0x00..=0xFF).filter_map(|byte| Self::from_byte_pair((0xFE, byte)).ok());
+        one_byte
+            .chain(two_bytes)
+            .filter(|opcode| opcode.name != "unused" && opcode.opcode_kind != OpcodeKind::Internal)
+            .find(|opcode| opcode.name == name)
+    }
+    /// Long form of a short branch, e.g. `BR` for `BR_S`.
+    pub fn long_form(&self) -> Option<Self> {
+        match (self.byte_1, self.byte_2) {
+            (0xFF, 0x2B..=0x37) => Some(Self::from_byte(self.byte_2 + 0x0D)),
+            (0xFF, 0xDE) => Some(LEAVE),
+            _ => None,
+        }
+    }
+    /// Short form of a long branch, e.g. `BR_S` for `BR`.
+    pub fn short_form(&self) -> Option<Self> {
+        match (self.byte_1, self.byte_2) {
+            (0xFF, 0x38..=0x44) => Some(Self::from_byte(self.byte_2 - 0x0D)),
+            (0xFF, 0xDD) => Some(LEAVE_S),
+            _ => None,
+        }
+    }
     pub fn from_byte(byte: u8) -> Self {
         match byte {
             0x00 => NOP,
@@ -0,0 +1,134 @@
+use crate::cil::{Instruction, Method, Opcode, Operand, Token};
+use std::ops::Range;
+
+type Predicate = Box<dyn Fn(&Operand) -> bool>;
+
+/// Instruction of a `Pattern`: one of `opcodes`, or any opcode when empty,
+/// with an operand accepted by every predicate.
+struct Step {
+    opcodes: Vec<Opcode>,
+    predicates: Vec<Predicate>,
+    capture: bool,
+}
+impl Step {
+    fn matches(&self, instruction: &Instruction) -> bool {
+        (self.opcodes.is_empty() || self.opcodes.contains(&instruction.opcode))
+            && self
+                .predicates
+                .iter()
+                .all(|predicate| predicate(&instruction.operand))
+    }
+}
+
+/// Sequence of consecutive instructions to look for with `Method::find`, e.g.
+/// a string passed to a call:
+/// ```
+/// # use clr_profiler::cil::{Pattern, Token, CALL, LDSTR};
+/// let log = Token::member_ref(0x0A00_0012).unwrap();
+/// let pattern = Pattern::new().opcode(LDSTR).capture().opcode(CALL).token(log);
+/// ```
+/// Operand restrictions and captures apply to the instruction added last.
+#[derive(Default)]
+pub struct Pattern {
+    steps: Vec<Step>,
+}
+impl Pattern {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds an instruction with `opcode`.
+    pub fn opcode(self, opcode: Opcode) -> Self {
+        self.any_of(&[opcode])
+    }
+    /// Adds an instruction with any of `opcodes`, e.g. both forms of a branch.
+    pub fn any_of(mut self, opcodes: &[Opcode]) -> Self {
+        self.steps.push(Step {
+            opcodes: opcodes.to_vec(),
+            predicates: Vec::new(),
+            capture: false,
+        });
+        self
+    }
+    /// Adds an instruction with any opcode.
+    pub fn any(self) -> Self {
+        self.any_of(&[])
+    }
+    /// Only matches when `predicate` accepts the operand.
+    ///
+    /// Panics if no instruction was added yet.
+    pub fn operand<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Operand) -> bool + 'static,
+    {
+        self.last().predicates.push(Box::new(predicate));
+        self
+    }
+    /// Only matches when the operand refers to `token`.
+    ///
+    /// Panics if no instruction was added yet.
+    pub fn token(self, token: Token) -> Self {
+        self.operand(move |operand| operand.token() == Some(token))
+    }
+    /// Reports the operand in `PatternMatch::captures`.
+    ///
+    /// Panics if no instruction was added yet.
+    pub fn capture(mut self) -> Self {
+        self.last().capture = true;
+        self
+    }
+    /// Whether the instructions starting at `start` match.
+    pub fn matches_at(&self, instructions: &[Instruction], start: usize) -> bool {
+        start + self.steps.len() <= instructions.len()
+            && self
+                .steps
+                .iter()
+                .zip(&instructions[start..])
+                .all(|(step, instruction)| step.matches(instruction))
+    }
+    /// Non-overlapping matches in `instructions`, from the first.
+    pub fn find_in(&self, instructions: &[Instruction]) -> Vec<PatternMatch> {
+        let mut matches = Vec::new();
+        if self.steps.is_empty() {
+            return matches;
+        }
+        let mut start = 0;
+        while start < instructions.len() {
+            if !self.matches_at(instructions, start) {
+                start += 1;
+                continue;
+            }
+            let range = start..start + self.steps.len();
+            let captures = self
+                .steps
+                .iter()
+                .zip(&instructions[range.clone()])
+                .filter(|(step, _)| step.capture)
+                .map(|(_, instruction)| instruction.operand.clone())
+                .collect();
+            start = range.end;
+            matches.push(PatternMatch { range, captures });
+        }
+        matches
+    }
+    fn last(&mut self) -> &mut Step {
+        self.steps
+            .last_mut()
+            .expect("no instruction to restrict, add one first")
+    }
+}
+
+/// Instructions matched by a `Pattern`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternMatch {
+    /// Indices of the matched instructions.
+    pub range: Range<usize>,
+    /// Operands of the capturing instructions, in pattern order.
+    pub captures: Vec<Operand>,
+}
+
+impl Method {
+    /// Non-overlapping matches of `pattern` in the instructions, from the first.
+    pub fn find(&self, pattern: &Pattern) -> Vec<PatternMatch> {
+        pattern.find_in(&self.instructions)
+    }
+}
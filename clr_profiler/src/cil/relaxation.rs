@@ -0,0 +1,60 @@
+use crate::cil::{flow::Layout, Error, Instruction, Operand};
+use std::convert::TryFrom;
+
+/// Widens every short branch whose label target is out of the `i8` range into
+/// its long form, until the layout is stable. Widening a branch grows the code
+/// between other branches and their targets, so a single pass is not enough.
+pub fn widen_branches(instructions: &mut [Instruction]) -> Result<(), Error> {
+    loop {
+        let layout = Layout::new(instructions)?;
+        let offsets = &layout.offsets;
+        let mut widened = false;
+        for (index, instruction) in instructions.iter_mut().enumerate() {
+            let label = match (&instruction.operand, instruction.opcode.long_form()) {
+                (Operand::BrTarget(label), Some(_)) => *label,
+                _ => continue,
+            };
+            let next = offsets[index] + instruction.length();
+            let target = offsets[layout.position(label)?];
+            if i8::try_from(target as i64 - next as i64).is_err() {
+                instruction.opcode = instruction.opcode.long_form().unwrap();
+                widened = true;
+            }
+        }
+        if !widened {
+            return Ok(());
+        }
+    }
+}
+
+/// Narrows every long branch whose label target is within the `i8` range into
+/// its short form, until the layout is stable. This is only a size optimization,
+/// shrinking a branch never pushes another one out of range.
+pub fn shrink_branches(instructions: &mut [Instruction]) -> Result<(), Error> {
+    loop {
+        let layout = Layout::new(instructions)?;
+        let offsets = &layout.offsets;
+        let mut shrunk = false;
+        for (index, instruction) in instructions.iter_mut().enumerate() {
+            let (label, short_form) = match (&instruction.operand, instruction.opcode.short_form())
+            {
+                (Operand::BrTarget(label), Some(short_form)) => (*label, short_form),
+                _ => continue,
+            };
+            let saved = (instruction.length() - short_form.length as usize - 1) as i64;
+            let next = (offsets[index] + instruction.length()) as i64 - saved;
+            let target_index = layout.position(label)?;
+            let target = match target_index > index {
+                true => offsets[target_index] as i64 - saved,
+                false => offsets[target_index] as i64,
+            };
+            if i8::try_from(target - next).is_ok() {
+                instruction.opcode = short_form;
+                shrunk = true;
+            }
+        }
+        if !shrunk {
+            return Ok(());
+        }
+    }
+}
@@ -1,5 +1,5 @@
 #![allow(non_upper_case_globals)]
-use crate::cil::{check_flag, il_u16, il_u32, il_u8, Error};
+use crate::cil::{check_flag, il_u16, il_u32, il_u8, Error, Label};
 
 bitflags! {
     pub struct SectionHeaderFlags: u8 {
@@ -17,7 +17,30 @@ bitflags! {
         const COR_ILEXCEPTION_CLAUSE_FAULT = 0x4;
     }
 }
-#[derive(Debug)]
+/// Labels marking the regions of an exception handling clause. Regions run
+/// from their start label up to and including the instruction at their last
+/// label, so code inserted right before or after a region stays outside of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClauseLabels {
+    pub try_start: Label,
+    pub try_last: Label,
+    pub handler_start: Label,
+    pub handler_last: Label,
+    pub filter_start: Option<Label>,
+}
+impl ClauseLabels {
+    /// Labels of the first instruction of each region.
+    pub fn starts(&self) -> Vec<Label> {
+        let mut starts = vec![self.try_start, self.handler_start];
+        starts.extend(self.filter_start);
+        starts
+    }
+    /// Labels of the last instruction of each region.
+    pub fn lasts(&self) -> Vec<Label> {
+        vec![self.try_last, self.handler_last]
+    }
+}
+#[derive(Debug, Clone, PartialEq)]
 pub struct FatSectionHeader {
     pub is_eh_table: bool,
     pub more_sects: bool,
@@ -25,8 +48,10 @@ pub struct FatSectionHeader {
     /// Must take care when converting back to CIL bytes.
     pub data_size: u32,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FatSectionClause {
+    /// `COR_ILEXCEPTION_CLAUSE_EXCEPTION` is 0, so every parsed clause has it
+    /// set. Clauses built in code set it as well to compare equal once parsed.
     pub is_exception: bool,
     pub is_filter: bool,
     pub is_finally: bool,
@@ -36,6 +61,9 @@ pub struct FatSectionClause {
     pub handler_offset: u32,
     pub handler_length: u32,
     pub class_token_or_filter_offset: u32,
+    /// When set, the offsets and lengths above are recomputed from these labels
+    /// whenever the owning `Method` is serialized.
+    pub labels: Option<ClauseLabels>,
 }
 impl FatSectionClause {
     const LENGTH: usize = 24;
@@ -72,17 +100,35 @@ impl FatSectionClause {
             handler_offset,
             handler_length,
             class_token_or_filter_offset,
+            labels: None,
         })
     }
 }
-#[derive(Debug)]
+impl From<&SmallSectionClause> for FatSectionClause {
+    fn from(clause: &SmallSectionClause) -> Self {
+        FatSectionClause {
+            is_exception: clause.is_exception,
+            is_filter: clause.is_filter,
+            is_finally: clause.is_finally,
+            is_fault: clause.is_fault,
+            try_offset: clause.try_offset as u32,
+            try_length: clause.try_length as u32,
+            handler_offset: clause.handler_offset as u32,
+            handler_length: clause.handler_length as u32,
+            class_token_or_filter_offset: clause.class_token_or_filter_offset,
+            labels: clause.labels,
+        }
+    }
+}
+#[derive(Debug, Clone, PartialEq)]
 pub struct SmallSectionHeader {
     pub is_eh_table: bool,
     pub more_sects: bool,
     pub data_size: u8,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SmallSectionClause {
+    /// Always set, see `FatSectionClause::is_exception`.
     pub is_exception: bool,
     pub is_filter: bool,
     pub is_finally: bool,
@@ -92,6 +138,9 @@ pub struct SmallSectionClause {
     pub handler_offset: u16,
     pub handler_length: u8,
     pub class_token_or_filter_offset: u32,
+    /// When set, the offsets and lengths above are recomputed from these labels
+    /// whenever the owning `Method` is serialized.
+    pub labels: Option<ClauseLabels>,
 }
 impl SmallSectionClause {
     const LENGTH: usize = 12;
@@ -128,17 +177,18 @@ impl SmallSectionClause {
             handler_offset,
             handler_length,
             class_token_or_filter_offset,
+            labels: None,
         })
     }
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Section {
     FatSection(FatSectionHeader, Vec<FatSectionClause>),
     SmallSection(SmallSectionHeader, Vec<SmallSectionClause>),
 }
 impl Section {
     pub fn from_bytes(il: &[u8]) -> Result<Self, Error> {
-        let header_flags = il[0];
+        let header_flags = il_u8(il, 0)?;
         let is_eh_table = Self::is_eh_table(header_flags);
         let more_sects = Self::more_sects(header_flags);
         if Self::is_small(header_flags) {
@@ -148,7 +198,9 @@ impl Section {
                 more_sects,
                 data_size,
             };
-            let clause_bytes = &il[4..(data_size as usize)];
+            let clause_bytes = il
+                .get(4..(data_size as usize))
+                .ok_or(Error::InvalidSectionHeader)?;
             let clauses = Self::get_small_clauses(clause_bytes)?;
             Ok(Section::SmallSection(small_header, clauses))
         } else if Self::is_fat(header_flags) {
@@ -161,7 +213,9 @@ impl Section {
                 more_sects,
                 data_size,
             };
-            let clause_bytes = &il[4..(data_size as usize)];
+            let clause_bytes = il
+                .get(4..(data_size as usize))
+                .ok_or(Error::InvalidSectionHeader)?;
             let clauses = Self::get_fat_clauses(clause_bytes)?;
             Ok(Section::FatSection(fat_header, clauses))
         } else {
@@ -240,12 +294,51 @@ impl Section {
         }
         bytes
     }
+    /// Promotes a small section into an equivalent fat one.
+    pub fn expand(&mut self) {
+        if let Section::SmallSection(header, clauses) = self {
+            let fat_header = FatSectionHeader {
+                is_eh_table: header.is_eh_table,
+                more_sects: header.more_sects,
+                data_size: header.data_size as u32,
+            };
+            let clauses = clauses.iter().map(FatSectionClause::from).collect();
+            *self = Section::FatSection(fat_header, clauses);
+            self.update_header(self.more_sects_flag());
+        }
+    }
+    /// Recomputes `data_size` from the clauses and sets the `more_sects` flag.
+    /// A small section whose clauses no longer fit its one byte size is promoted.
+    pub fn update_header(&mut self, more_sects: bool) {
+        match self {
+            Section::FatSection(header, clauses) => {
+                header.more_sects = more_sects;
+                header.data_size = (4 + clauses.len() * FatSectionClause::LENGTH) as u32;
+            }
+            Section::SmallSection(header, clauses) => {
+                let data_size = 4 + clauses.len() * SmallSectionClause::LENGTH;
+                if data_size > u8::MAX as usize {
+                    self.expand();
+                    self.update_header(more_sects);
+                } else {
+                    header.more_sects = more_sects;
+                    header.data_size = data_size as u8;
+                }
+            }
+        }
+    }
     pub fn data_size(&self) -> usize {
         match self {
             Self::FatSection(header, _) => header.data_size as usize,
             Self::SmallSection(header, _) => header.data_size as usize,
         }
     }
+    fn more_sects_flag(&self) -> bool {
+        match self {
+            Self::FatSection(header, _) => header.more_sects,
+            Self::SmallSection(header, _) => header.more_sects,
+        }
+    }
     fn is_small(section_header_flags: u8) -> bool {
         !Self::is_fat(section_header_flags)
     }
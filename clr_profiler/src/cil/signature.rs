@@ -0,0 +1,512 @@
+use crate::{
+    cil::{compress_i32, compress_u32, il_compressed_u32, Error, Token, TokenTable},
+    ffi::CorElementType,
+};
+use std::convert::TryFrom;
+
+const HAS_THIS: u8 = 0x20;
+const EXPLICIT_THIS: u8 = 0x40;
+const GENERIC: u8 = 0x10;
+const FIELD: u8 = 0x06;
+const LOCAL_SIG: u8 = 0x07;
+const PROPERTY: u8 = 0x08;
+const GENERIC_INST: u8 = 0x0A;
+const SENTINEL: u8 = 0x41;
+/// Deepest nesting of types accepted, so hostile blobs can't exhaust the stack.
+const MAX_DEPTH: usize = 64;
+
+/// Calling convention of a method signature, held in the low bits of its
+/// first byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallingConvention {
+    Default = 0x00,
+    C = 0x01,
+    StdCall = 0x02,
+    ThisCall = 0x03,
+    FastCall = 0x04,
+    VarArg = 0x05,
+    Unmanaged = 0x09,
+}
+
+/// Type in a signature blob.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    /// `void`, `bool`, `char`, numbers, `string`, `object`, `typedref`,
+    /// `native int` and `native uint`.
+    Primitive(CorElementType),
+    Class(Token),
+    ValueType(Token),
+    /// Instantiation of the generic class or value type `generic`.
+    GenericInst {
+        value_type: bool,
+        generic: Token,
+        args: Vec<Type>,
+    },
+    Ptr(Box<Type>),
+    ByRef(Box<Type>),
+    /// Single dimensional array with a zero lower bound, e.g. `int32[]`.
+    SzArray(Box<Type>),
+    Array(Box<Type>, ArrayShape),
+    /// Generic parameter of the type, by index.
+    Var(u32),
+    /// Generic parameter of the method, by index.
+    MVar(u32),
+    FnPtr(Box<MethodSignature>),
+    /// Type with a custom modifier, such as `modreq(IsVolatile)`.
+    Modified {
+        required: bool,
+        modifier: Token,
+        modified: Box<Type>,
+    },
+    /// Local pinned for the garbage collector.
+    Pinned(Box<Type>),
+}
+
+/// Dimensions of a general array. Dimensions past `sizes` and `lower_bounds`
+/// are unspecified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrayShape {
+    pub rank: u32,
+    pub sizes: Vec<u32>,
+    pub lower_bounds: Vec<i32>,
+}
+
+/// Signature of a method definition, reference, call site or function pointer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodSignature {
+    pub has_this: bool,
+    pub explicit_this: bool,
+    pub calling_convention: CallingConvention,
+    /// Number of generic parameters, 0 for methods which aren't generic.
+    pub generic_param_count: u32,
+    pub return_type: Type,
+    pub params: Vec<Type>,
+    /// Index in `params` of the first extra argument of a `VarArg` call site.
+    pub sentinel: Option<usize>,
+}
+impl MethodSignature {
+    /// Signature with the default calling convention, of a static method or,
+    /// with `has_this`, of an instance method.
+    pub fn new(has_this: bool, return_type: Type, params: Vec<Type>) -> Self {
+        MethodSignature {
+            has_this,
+            explicit_this: false,
+            calling_convention: CallingConvention::Default,
+            generic_param_count: 0,
+            return_type,
+            params,
+            sentinel: None,
+        }
+    }
+    pub fn from_bytes(blob: &[u8]) -> Result<Self, Error> {
+        match Signature::from_bytes(blob)? {
+            Signature::Method(signature) => Ok(signature),
+            _ => Err(Error::InvalidSignature),
+        }
+    }
+    /// Arguments taken from the stack, including an implicit `this`.
+    pub fn arg_count(&self) -> usize {
+        let this = self.has_this && !self.explicit_this;
+        self.params.len() + this as usize
+    }
+    /// Encodes the signature, e.g. for `DefineMethod`, `DefineMemberRef` or,
+    /// for `calli`, `get_token_from_sig`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut blob = Vec::new();
+        self.write(&mut blob)?;
+        Ok(blob)
+    }
+    fn write(&self, blob: &mut Vec<u8>) -> Result<(), Error> {
+        let mut first = self.calling_convention as u8;
+        if self.has_this {
+            first |= HAS_THIS;
+        }
+        if self.explicit_this {
+            first |= EXPLICIT_THIS;
+        }
+        if self.generic_param_count > 0 {
+            first |= GENERIC;
+        }
+        blob.push(first);
+        if self.generic_param_count > 0 {
+            blob.extend(compress_u32(self.generic_param_count)?);
+        }
+        write_count(blob, self.params.len())?;
+        self.return_type.write(blob)?;
+        match self.sentinel {
+            Some(sentinel) if sentinel >= self.params.len() => return Err(Error::InvalidSignature),
+            _ => (),
+        }
+        for (index, param) in self.params.iter().enumerate() {
+            if self.sentinel == Some(index) {
+                blob.push(SENTINEL);
+            }
+            param.write(blob)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertySignature {
+    pub has_this: bool,
+    pub property_type: Type,
+    /// Parameters of an indexer.
+    pub params: Vec<Type>,
+}
+
+/// Decoded signature blob, as returned along with method, field and member
+/// reference properties, or by `get_sig_from_token`. TypeSpec blobs hold a
+/// single type, see `Type::from_bytes`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Signature {
+    Method(MethodSignature),
+    Field(Type),
+    Property(PropertySignature),
+    LocalVar(Vec<Type>),
+    /// Type arguments of a generic method instantiation.
+    MethodSpec(Vec<Type>),
+}
+impl Signature {
+    pub fn from_bytes(blob: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(blob);
+        let first = reader.u8()?;
+        let signature = match first & 0x0F {
+            FIELD => Signature::Field(reader.type_()?),
+            LOCAL_SIG => Signature::LocalVar(reader.types()?),
+            PROPERTY => {
+                let param_count = reader.compressed()?;
+                let property_type = reader.type_()?;
+                let params = (0..param_count)
+                    .map(|_| reader.type_())
+                    .collect::<Result<_, _>>()?;
+                Signature::Property(PropertySignature {
+                    has_this: first & HAS_THIS == HAS_THIS,
+                    property_type,
+                    params,
+                })
+            }
+            GENERIC_INST => Signature::MethodSpec(reader.types()?),
+            _ => Signature::Method(reader.method(first)?),
+        };
+        reader.end()?;
+        Ok(signature)
+    }
+    /// Encodes the signature, e.g. for `get_token_from_sig` or `DefineMemberRef`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut blob = Vec::new();
+        match self {
+            Signature::Method(signature) => signature.write(&mut blob)?,
+            Signature::Field(type_) => {
+                blob.push(FIELD);
+                type_.write(&mut blob)?;
+            }
+            Signature::Property(signature) => {
+                blob.push(match signature.has_this {
+                    true => PROPERTY | HAS_THIS,
+                    false => PROPERTY,
+                });
+                write_count(&mut blob, signature.params.len())?;
+                signature.property_type.write(&mut blob)?;
+                write_types(&mut blob, &signature.params)?;
+            }
+            Signature::LocalVar(types) => {
+                blob.push(LOCAL_SIG);
+                write_count(&mut blob, types.len())?;
+                write_types(&mut blob, types)?;
+            }
+            Signature::MethodSpec(types) => {
+                blob.push(GENERIC_INST);
+                write_count(&mut blob, types.len())?;
+                write_types(&mut blob, types)?;
+            }
+        }
+        Ok(blob)
+    }
+}
+
+impl Type {
+    /// Decodes a TypeSpec blob.
+    pub fn from_bytes(blob: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(blob);
+        let type_ = reader.type_()?;
+        reader.end()?;
+        Ok(type_)
+    }
+    /// Encodes the type as a TypeSpec blob.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut blob = Vec::new();
+        self.write(&mut blob)?;
+        Ok(blob)
+    }
+    fn write(&self, blob: &mut Vec<u8>) -> Result<(), Error> {
+        match self {
+            Type::Primitive(element) => match *element as u8 {
+                element @ (0x01..=0x0E | 0x16 | 0x18 | 0x19 | 0x1C) => blob.push(element),
+                _ => return Err(Error::InvalidSignature),
+            },
+            Type::Class(token) => {
+                blob.push(0x12);
+                blob.extend(compress_type_token(*token)?);
+            }
+            Type::ValueType(token) => {
+                blob.push(0x11);
+                blob.extend(compress_type_token(*token)?);
+            }
+            Type::GenericInst {
+                value_type,
+                generic,
+                args,
+            } => {
+                blob.push(0x15);
+                blob.push(if *value_type { 0x11 } else { 0x12 });
+                blob.extend(compress_type_token(*generic)?);
+                write_count(blob, args.len())?;
+                write_types(blob, args)?;
+            }
+            Type::Ptr(type_) => {
+                blob.push(0x0F);
+                type_.write(blob)?;
+            }
+            Type::ByRef(type_) => {
+                blob.push(0x10);
+                type_.write(blob)?;
+            }
+            Type::SzArray(type_) => {
+                blob.push(0x1D);
+                type_.write(blob)?;
+            }
+            Type::Array(type_, shape) => {
+                blob.push(0x14);
+                type_.write(blob)?;
+                blob.extend(compress_u32(shape.rank)?);
+                write_count(blob, shape.sizes.len())?;
+                for size in shape.sizes.iter() {
+                    blob.extend(compress_u32(*size)?);
+                }
+                write_count(blob, shape.lower_bounds.len())?;
+                for lower_bound in shape.lower_bounds.iter() {
+                    blob.extend(compress_i32(*lower_bound)?);
+                }
+            }
+            Type::Var(index) => {
+                blob.push(0x13);
+                blob.extend(compress_u32(*index)?);
+            }
+            Type::MVar(index) => {
+                blob.push(0x1E);
+                blob.extend(compress_u32(*index)?);
+            }
+            Type::FnPtr(signature) => {
+                blob.push(0x1B);
+                signature.write(blob)?;
+            }
+            Type::Modified {
+                required,
+                modifier,
+                modified,
+            } => {
+                blob.push(if *required { 0x1F } else { 0x20 });
+                blob.extend(compress_type_token(*modifier)?);
+                modified.write(blob)?;
+            }
+            Type::Pinned(type_) => {
+                blob.push(0x45);
+                type_.write(blob)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Encodes a TypeDef, TypeRef or TypeSpec token the way signatures refer to
+/// types: the row id followed by the table in the low 2 bits, compressed.
+pub fn compress_type_token(token: Token) -> Result<Vec<u8>, Error> {
+    let table = match token.table() {
+        TokenTable::TypeDef => 0,
+        TokenTable::TypeRef => 1,
+        TokenTable::TypeSpec => 2,
+        _ => return Err(Error::InvalidToken(token.raw())),
+    };
+    compress_u32(token.rid() << 2 | table)
+}
+
+fn write_count(blob: &mut Vec<u8>, count: usize) -> Result<(), Error> {
+    let count = u32::try_from(count).or(Err(Error::InvalidSignature))?;
+    blob.extend(compress_u32(count)?);
+    Ok(())
+}
+
+fn write_types(blob: &mut Vec<u8>, types: &[Type]) -> Result<(), Error> {
+    for type_ in types {
+        type_.write(blob)?;
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    blob: &'a [u8],
+    index: usize,
+    depth: usize,
+}
+impl<'a> Reader<'a> {
+    fn new(blob: &'a [u8]) -> Self {
+        Reader {
+            blob,
+            index: 0,
+            depth: 0,
+        }
+    }
+    fn peek(&self) -> Result<u8, Error> {
+        self.blob
+            .get(self.index)
+            .copied()
+            .ok_or(Error::InvalidSignature)
+    }
+    fn u8(&mut self) -> Result<u8, Error> {
+        let byte = self.peek()?;
+        self.index += 1;
+        Ok(byte)
+    }
+    fn compressed(&mut self) -> Result<u32, Error> {
+        let (value, length) =
+            il_compressed_u32(self.blob, self.index).or(Err(Error::InvalidSignature))?;
+        self.index += length;
+        Ok(value)
+    }
+    /// Compressed signed integers are rotated so the sign ends up in bit 0.
+    fn compressed_i32(&mut self) -> Result<i32, Error> {
+        let start = self.index;
+        let value = self.compressed()?;
+        let bits = match self.index - start {
+            1 => 6,
+            2 => 13,
+            _ => 28,
+        };
+        let magnitude = (value >> 1) as i32;
+        match value & 1 {
+            0 => Ok(magnitude),
+            _ => Ok(magnitude | (-1 << bits)),
+        }
+    }
+    /// TypeDefOrRefOrSpecEncoded token: the table in the low 2 bits.
+    fn type_token(&mut self) -> Result<Token, Error> {
+        let value = self.compressed()?;
+        let table = match value & 0x03 {
+            0 => TokenTable::TypeDef,
+            1 => TokenTable::TypeRef,
+            2 => TokenTable::TypeSpec,
+            _ => return Err(Error::InvalidSignature),
+        };
+        Token::new(table, value >> 2).or(Err(Error::InvalidSignature))
+    }
+    /// Count followed by as many types.
+    fn types(&mut self) -> Result<Vec<Type>, Error> {
+        let count = self.compressed()?;
+        (0..count).map(|_| self.type_()).collect()
+    }
+    fn type_(&mut self) -> Result<Type, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::InvalidSignature);
+        }
+        self.depth += 1;
+        let element = self.u8()?;
+        let type_ = match element {
+            0x01..=0x0E | 0x16 | 0x18 | 0x19 | 0x1C => {
+                Type::Primitive(CorElementType::from(element as u32))
+            }
+            0x0F => Type::Ptr(Box::new(self.type_()?)),
+            0x10 => Type::ByRef(Box::new(self.type_()?)),
+            0x11 => Type::ValueType(self.type_token()?),
+            0x12 => Type::Class(self.type_token()?),
+            0x13 => Type::Var(self.compressed()?),
+            0x14 => {
+                let element = self.type_()?;
+                let rank = self.compressed()?;
+                let sizes = (0..self.compressed()?)
+                    .map(|_| self.compressed())
+                    .collect::<Result<_, _>>()?;
+                let lower_bounds = (0..self.compressed()?)
+                    .map(|_| self.compressed_i32())
+                    .collect::<Result<_, _>>()?;
+                let shape = ArrayShape {
+                    rank,
+                    sizes,
+                    lower_bounds,
+                };
+                Type::Array(Box::new(element), shape)
+            }
+            0x15 => {
+                let value_type = match self.u8()? {
+                    0x11 => true,
+                    0x12 => false,
+                    _ => return Err(Error::InvalidSignature),
+                };
+                let generic = self.type_token()?;
+                let args = self.types()?;
+                Type::GenericInst {
+                    value_type,
+                    generic,
+                    args,
+                }
+            }
+            0x1B => {
+                let first = self.u8()?;
+                Type::FnPtr(Box::new(self.method(first)?))
+            }
+            0x1D => Type::SzArray(Box::new(self.type_()?)),
+            0x1E => Type::MVar(self.compressed()?),
+            0x1F | 0x20 => Type::Modified {
+                required: element == 0x1F,
+                modifier: self.type_token()?,
+                modified: Box::new(self.type_()?),
+            },
+            0x45 => Type::Pinned(Box::new(self.type_()?)),
+            _ => return Err(Error::InvalidSignature),
+        };
+        self.depth -= 1;
+        Ok(type_)
+    }
+    fn method(&mut self, first: u8) -> Result<MethodSignature, Error> {
+        let calling_convention = match first & 0x0F {
+            0x00 => CallingConvention::Default,
+            0x01 => CallingConvention::C,
+            0x02 => CallingConvention::StdCall,
+            0x03 => CallingConvention::ThisCall,
+            0x04 => CallingConvention::FastCall,
+            0x05 => CallingConvention::VarArg,
+            0x09 => CallingConvention::Unmanaged,
+            _ => return Err(Error::InvalidSignature),
+        };
+        let generic_param_count = match first & GENERIC {
+            GENERIC => self.compressed()?,
+            _ => 0,
+        };
+        let param_count = self.compressed()? as usize;
+        let return_type = self.type_()?;
+        let mut params = Vec::new();
+        let mut sentinel = None;
+        while params.len() < param_count {
+            if self.peek()? == SENTINEL && sentinel.is_none() {
+                self.index += 1;
+                sentinel = Some(params.len());
+            }
+            params.push(self.type_()?);
+        }
+        Ok(MethodSignature {
+            has_this: first & HAS_THIS == HAS_THIS,
+            explicit_this: first & EXPLICIT_THIS == EXPLICIT_THIS,
+            calling_convention,
+            generic_param_count,
+            return_type,
+            params,
+            sentinel,
+        })
+    }
+    fn end(&self) -> Result<(), Error> {
+        match self.index == self.blob.len() {
+            true => Ok(()),
+            false => Err(Error::InvalidSignature),
+        }
+    }
+}
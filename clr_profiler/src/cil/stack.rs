@@ -0,0 +1,119 @@
+use crate::{
+    cil::{
+        flow, ControlFlow, Error, Instruction, MethodSignature, Operand, StackBehaviorPop,
+        StackBehaviorPush, Type, CALLI, NEWOBJ,
+    },
+    ffi::CorElementType,
+};
+
+impl StackBehaviorPop {
+    /// Number of popped slots, `None` for `VarPop`.
+    pub fn count(&self) -> Option<usize> {
+        use StackBehaviorPop::*;
+        match self {
+            Pop0 => Some(0),
+            Pop1 | PopI | PopRef => Some(1),
+            Pop1Pop1 | PopIPopI | PopIPopI8 | PopIPopR4 | PopIPopR8 | PopRefPop1 | PopIPop1
+            | PopRefPopI => Some(2),
+            PopRefPopIPopI | PopRefPopIPopI8 | PopRefPopIPopR4 | PopRefPopIPopR8
+            | PopRefPopIPopRef | PopRefPopIPop1 | PopIPopIPopI => Some(3),
+            VarPop => None,
+        }
+    }
+}
+impl StackBehaviorPush {
+    /// Number of pushed slots, `None` for `VarPush`.
+    pub fn count(&self) -> Option<usize> {
+        use StackBehaviorPush::*;
+        match self {
+            Push0 => Some(0),
+            Push1 | PushI | PushRef | PushI8 | PushR4 | PushR8 => Some(1),
+            Push1Push1 => Some(2),
+            VarPush => None,
+        }
+    }
+}
+
+/// Deepest evaluation stack reached on any path from the `entries`, given as
+/// instruction indices with the stack depth on entry. Call sites pop and push
+/// according to the signature `signature` returns for their token.
+pub(crate) fn max_depth<F>(
+    instructions: &[Instruction],
+    entries: Vec<(usize, usize)>,
+    mut signature: F,
+) -> Result<usize, Error>
+where
+    F: FnMut(u32) -> Option<Vec<u8>>,
+{
+    let layout = flow::Layout::new(instructions)?;
+    let mut depths = vec![None; instructions.len()];
+    let mut pending = entries;
+    let mut max_depth = 0;
+    while let Some((index, depth)) = pending.pop() {
+        // Falling off the end is left for verification to report
+        if index >= instructions.len() || depths[index].is_some() {
+            continue;
+        }
+        depths[index] = Some(depth);
+        max_depth = max_depth.max(depth);
+        let instruction = &instructions[index];
+        let (pops, pushes) = stack_behavior(instruction, &mut signature)?;
+        let depth = depth
+            .checked_sub(pops)
+            .ok_or(Error::StackUnderflow(index))?
+            + pushes;
+        max_depth = max_depth.max(depth);
+        let depth = if flow::is_leave(instruction) {
+            0
+        } else {
+            depth
+        };
+        for successor in layout.successors(instructions, index)? {
+            pending.push((successor, depth));
+        }
+    }
+    Ok(max_depth)
+}
+
+pub(crate) fn stack_behavior<F>(
+    instruction: &Instruction,
+    signature: &mut F,
+) -> Result<(usize, usize), Error>
+where
+    F: FnMut(u32) -> Option<Vec<u8>>,
+{
+    let opcode = &instruction.opcode;
+    match (
+        opcode.stack_behavior_pop.count(),
+        opcode.stack_behavior_push.count(),
+    ) {
+        (Some(pops), Some(pushes)) => return Ok((pops, pushes)),
+        // `ret` ends the flow, whatever it pops does not matter for the depth
+        (None, Some(0)) if opcode.control_flow == ControlFlow::Return => return Ok((0, 0)),
+        _ => (),
+    }
+    let token = match instruction.operand {
+        Operand::InlineMethod(token) => token.raw(),
+        Operand::InlineSig(token) => token,
+        _ => return Err(Error::InvalidCil),
+    };
+    let sig = signature(token).ok_or(Error::UnresolvedSignature(token))?;
+    let sig = MethodSignature::from_bytes(&sig)?;
+    // Custom modifiers don't change what is returned
+    let mut return_type = &sig.return_type;
+    while let Type::Modified { modified, .. } = return_type {
+        return_type = modified;
+    }
+    let returns_value = *return_type != Type::Primitive(CorElementType::ELEMENT_TYPE_VOID);
+    let pushes = opcode
+        .stack_behavior_push
+        .count()
+        .unwrap_or(returns_value as usize);
+    let pops = match *opcode {
+        NEWOBJ => sig.params.len(),
+        // The function pointer comes on top of the arguments
+        CALLI => sig.arg_count() + 1,
+        _ => sig.arg_count(),
+    };
+    Ok((pops, pushes))
+}
@@ -0,0 +1,166 @@
+use crate::{
+    cil::{Error, OperandParams},
+    ffi::{
+        mdFieldDef, mdMemberRef, mdMethodDef, mdMethodSpec, mdSignature, mdString, mdToken,
+        mdTypeDef, mdTypeRef, mdTypeSpec,
+    },
+};
+use std::convert::TryFrom;
+
+/// Metadata table a token refers to, stored in the top byte of the token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenTable {
+    Module = 0x00,
+    TypeRef = 0x01,
+    TypeDef = 0x02,
+    FieldDef = 0x04,
+    MethodDef = 0x06,
+    ParamDef = 0x08,
+    InterfaceImpl = 0x09,
+    MemberRef = 0x0A,
+    CustomAttribute = 0x0C,
+    Permission = 0x0E,
+    Signature = 0x11,
+    Event = 0x14,
+    Property = 0x17,
+    ModuleRef = 0x1A,
+    TypeSpec = 0x1B,
+    Assembly = 0x20,
+    AssemblyRef = 0x23,
+    File = 0x26,
+    ExportedType = 0x27,
+    ManifestResource = 0x28,
+    GenericParam = 0x2A,
+    MethodSpec = 0x2B,
+    GenericParamConstraint = 0x2C,
+    UserString = 0x70,
+}
+impl TryFrom<u8> for TokenTable {
+    type Error = Error;
+    fn try_from(byte: u8) -> Result<Self, Error> {
+        use TokenTable::*;
+        let table = match byte {
+            0x00 => Module,
+            0x01 => TypeRef,
+            0x02 => TypeDef,
+            0x04 => FieldDef,
+            0x06 => MethodDef,
+            0x08 => ParamDef,
+            0x09 => InterfaceImpl,
+            0x0A => MemberRef,
+            0x0C => CustomAttribute,
+            0x0E => Permission,
+            0x11 => Signature,
+            0x14 => Event,
+            0x17 => Property,
+            0x1A => ModuleRef,
+            0x1B => TypeSpec,
+            0x20 => Assembly,
+            0x23 => AssemblyRef,
+            0x26 => File,
+            0x27 => ExportedType,
+            0x28 => ManifestResource,
+            0x2A => GenericParam,
+            0x2B => MethodSpec,
+            0x2C => GenericParamConstraint,
+            0x70 => UserString,
+            _ => return Err(Error::InvalidToken((byte as u32) << 24)),
+        };
+        Ok(table)
+    }
+}
+
+/// Metadata token which is known to refer to one of the `TokenTable`s.
+///
+/// Typed constructors such as `Token::member_ref` check the table of the
+/// `mdToken` they are given, so a `MethodDef` can't be passed off as a
+/// `MemberRef`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Token(mdToken);
+impl Token {
+    /// Token of the row `rid` of `table`. Row ids are 24 bits.
+    pub fn new(table: TokenTable, rid: u32) -> Result<Self, Error> {
+        let token = (table as u32) << 24 | rid;
+        match rid {
+            0..=0x00FF_FFFF => Ok(Token(token)),
+            _ => Err(Error::InvalidToken(token)),
+        }
+    }
+    /// Token of any known table.
+    pub fn from_raw(token: mdToken) -> Result<Self, Error> {
+        TokenTable::try_from((token >> 24) as u8).or(Err(Error::InvalidToken(token)))?;
+        Ok(Token(token))
+    }
+    pub fn type_ref(token: mdTypeRef) -> Result<Self, Error> {
+        Self::of(TokenTable::TypeRef, token)
+    }
+    pub fn type_def(token: mdTypeDef) -> Result<Self, Error> {
+        Self::of(TokenTable::TypeDef, token)
+    }
+    pub fn type_spec(token: mdTypeSpec) -> Result<Self, Error> {
+        Self::of(TokenTable::TypeSpec, token)
+    }
+    pub fn field_def(token: mdFieldDef) -> Result<Self, Error> {
+        Self::of(TokenTable::FieldDef, token)
+    }
+    pub fn method_def(token: mdMethodDef) -> Result<Self, Error> {
+        Self::of(TokenTable::MethodDef, token)
+    }
+    pub fn member_ref(token: mdMemberRef) -> Result<Self, Error> {
+        Self::of(TokenTable::MemberRef, token)
+    }
+    pub fn method_spec(token: mdMethodSpec) -> Result<Self, Error> {
+        Self::of(TokenTable::MethodSpec, token)
+    }
+    pub fn signature(token: mdSignature) -> Result<Self, Error> {
+        Self::of(TokenTable::Signature, token)
+    }
+    pub fn user_string(token: mdString) -> Result<Self, Error> {
+        Self::of(TokenTable::UserString, token)
+    }
+    /// Token of `table`, any other table is rejected.
+    pub fn of(table: TokenTable, token: mdToken) -> Result<Self, Error> {
+        match Self::from_raw(token)? {
+            token if token.table() == table => Ok(token),
+            _ => Err(Error::InvalidToken(token)),
+        }
+    }
+    pub fn table(&self) -> TokenTable {
+        // Every constructor checks the table
+        TokenTable::try_from((self.0 >> 24) as u8).unwrap()
+    }
+    /// Row id within the table, starting at 1 for actual rows.
+    pub fn rid(&self) -> u32 {
+        self.0 & 0x00FF_FFFF
+    }
+    pub fn raw(&self) -> mdToken {
+        self.0
+    }
+    /// Whether an instruction with these operand params may refer to the token,
+    /// e.g. `call` takes method definitions, references and instantiations.
+    pub fn fits(&self, operand_params: &OperandParams) -> bool {
+        use TokenTable::*;
+        match (operand_params, self.table()) {
+            (OperandParams::InlineMethod, MethodDef)
+            | (OperandParams::InlineMethod, MemberRef)
+            | (OperandParams::InlineMethod, MethodSpec)
+            | (OperandParams::InlineField, FieldDef)
+            | (OperandParams::InlineField, MemberRef)
+            | (OperandParams::InlineType, TypeDef)
+            | (OperandParams::InlineType, TypeRef)
+            | (OperandParams::InlineType, TypeSpec)
+            | (OperandParams::InlineString, UserString)
+            | (OperandParams::InlineSig, Signature) => true,
+            (OperandParams::InlineTok, table) => matches!(
+                table,
+                TypeDef | TypeRef | TypeSpec | MethodDef | MemberRef | MethodSpec | FieldDef
+            ),
+            _ => false,
+        }
+    }
+}
+impl From<Token> for mdToken {
+    fn from(token: Token) -> Self {
+        token.0
+    }
+}
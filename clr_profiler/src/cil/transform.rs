@@ -0,0 +1,283 @@
+use crate::{
+    cil::{
+        ClauseLabels, Error, FatSectionClause, FatSectionHeader, Instruction, Label, Method,
+        OpcodeKind, Operand, OperandParams, Section, Token, Type, CALL, CALLVIRT, CONSTRAINED, DUP,
+        ENDFINALLY, LEAVE_S, NEWOBJ, NOP, RET, TAILCALL,
+    },
+    ffi::CorElementType,
+    MetadataEmitTrait, MetadataImportTrait,
+};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::slice;
+
+/// Handler `Method::wrap` protects the whole body with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WrapHandler {
+    /// Runs whenever the body exits, normally or by throwing.
+    Finally,
+    /// Runs only when the body throws.
+    Fault,
+}
+
+/// Call sites `Method::redirect_calls` rewrites, and what they call instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallRedirect {
+    /// Method whose `call`, `callvirt` and `newobj` sites are redirected.
+    pub target: Token,
+    /// Method called instead. It takes the arguments of `target`, followed by
+    /// the ones pushed by `arguments`.
+    pub replacement: Token,
+    /// Pushes the extra arguments of `replacement`, e.g. a context object.
+    /// Inserted before every redirected call, can be empty.
+    pub arguments: Vec<Instruction>,
+    /// Calls `replacement` with `call`, for static wrappers. The instance of a
+    /// `callvirt` becomes their first argument, and wrappers of a constructor
+    /// return the new object instead of being called by `newobj`.
+    pub static_call: bool,
+}
+
+impl Method {
+    /// Makes every call site of `redirect.target` call `redirect.replacement`,
+    /// returning how many were redirected. Library methods are instrumented
+    /// this way in the bodies calling them, leaving the library itself as is.
+    ///
+    /// Branches and clause regions starting at a call site start at its
+    /// inserted arguments instead. A `constrained.` call can't be redirected to
+    /// a static wrapper. The max stack isn't updated, call `update_max_stack`
+    /// afterwards. A `replacement` that isn't a method is `Error::InvalidToken`.
+    pub fn redirect_calls(&mut self, redirect: &CallRedirect) -> Result<usize, Error> {
+        if !redirect.replacement.fits(&OperandParams::InlineMethod) {
+            return Err(Error::InvalidToken(redirect.replacement.raw()));
+        }
+        let prefixed = |index: usize| {
+            index > 0 && self.instructions[index - 1].opcode.opcode_kind == OpcodeKind::Prefix
+        };
+        // The arguments go before the prefixes of the call
+        let sites: Vec<Range<usize>> = self
+            .instructions
+            .iter()
+            .enumerate()
+            .filter(|(_, instruction)| {
+                [CALL, CALLVIRT, NEWOBJ].contains(&instruction.opcode)
+                    && instruction.operand == Operand::InlineMethod(redirect.target)
+            })
+            .map(|(index, _)| {
+                let mut start = index;
+                while prefixed(start) {
+                    start -= 1;
+                }
+                start..index
+            })
+            .collect();
+        let constrained = sites
+            .iter()
+            .flat_map(|site| &self.instructions[site.clone()])
+            .any(|prefix| prefix.opcode == CONSTRAINED);
+        if redirect.static_call && constrained {
+            return Err(Error::InvalidCil);
+        }
+        // From the back, so the indices of the remaining sites stay valid
+        for Range { start, end: index } in sites.iter().cloned().rev() {
+            let call = &mut self.instructions[index];
+            if redirect.static_call {
+                call.opcode = CALL;
+            }
+            call.operand = Operand::InlineMethod(redirect.replacement);
+            if redirect.arguments.is_empty() {
+                continue;
+            }
+            let site = self.instructions[start].label;
+            self.insert(start, redirect.arguments.clone())?;
+            if let Some(site) = site {
+                let arguments = self.label_at(start);
+                self.forward_starts(site, arguments);
+            }
+        }
+        Ok(sites.len())
+    }
+    /// Makes branches and clause regions starting at the instruction labelled
+    /// `from` start at the one labelled `to`. Regions ending at it are kept.
+    fn forward_starts(&mut self, from: Label, to: Label) {
+        let renamed: HashMap<_, _> = vec![(from, to)].into_iter().collect();
+        for instruction in self.instructions.iter_mut() {
+            Self::rename_targets(&mut instruction.operand, &renamed);
+        }
+        let forward = |label: &mut Label| {
+            if *label == from {
+                *label = to;
+            }
+        };
+        for labels in self.clause_labels_mut() {
+            forward(&mut labels.try_start);
+            forward(&mut labels.handler_start);
+            if let Some(filter_start) = labels.filter_start.as_mut() {
+                forward(filter_start);
+            }
+        }
+    }
+    /// Inserts a copy of `epilogue` before every `ret`, so it runs on every
+    /// path out of the method. Branches that landed on a `ret` land on its
+    /// epilogue instead.
+    ///
+    /// When `with_return_value` is set, the return value is duplicated before
+    /// each copy and the epilogue must consume it, e.g. by passing it to an
+    /// exit probe. Otherwise the epilogue must leave the stack as it found it.
+    /// `tail.` prefixes are dropped, since the call is no longer followed by the
+    /// `ret`. The max stack isn't updated, call `update_max_stack` afterwards.
+    pub fn insert_epilogue(
+        &mut self,
+        epilogue: Vec<Instruction>,
+        with_return_value: bool,
+    ) -> Result<(), Error> {
+        for instruction in self.instructions.iter_mut() {
+            if instruction.opcode == TAILCALL {
+                instruction.opcode = NOP;
+            }
+        }
+        let rets: Vec<usize> = self
+            .instructions
+            .iter()
+            .enumerate()
+            .filter(|(_, instruction)| instruction.opcode == RET)
+            .map(|(index, _)| index)
+            .collect();
+        // From the back, so the indices of the remaining `ret`s stay valid
+        for index in rets.into_iter().rev() {
+            let mut instructions = Vec::new();
+            if with_return_value {
+                instructions.push(Instruction::new(DUP, Operand::InlineNone));
+            }
+            instructions.extend(epilogue.iter().cloned());
+            if instructions.is_empty() {
+                continue;
+            }
+            let ret_label = self.instructions[index].label.take();
+            self.insert(index, instructions)?;
+            if let Some(ret_label) = ret_label {
+                let renamed: HashMap<_, _> = vec![(ret_label, self.label_at(index))]
+                    .into_iter()
+                    .collect();
+                for instruction in self.instructions.iter_mut() {
+                    Self::rename_targets(&mut instruction.operand, &renamed);
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Wraps the whole body in a new try block protected by `handler`, which
+    /// runs `epilogue`. This is how timing or tracing code is guaranteed to run
+    /// however the method exits.
+    ///
+    /// Every `ret` becomes a `leave` to a common exit placed after the handler.
+    /// Unless `return_type` is void, the return value is stashed in a new local
+    /// of that type, added like with `add_locals`, and its index is returned.
+    /// `tail.` prefixes are dropped, a call can't be a tail call from inside a
+    /// try block.
+    ///
+    /// `epilogue` is inserted like with `insert` and must leave the stack empty,
+    /// it doesn't need to end with `endfinally`. The max stack isn't updated,
+    /// call `update_max_stack` afterwards.
+    pub fn wrap<I, E>(
+        &mut self,
+        handler: WrapHandler,
+        mut epilogue: Vec<Instruction>,
+        return_type: &Type,
+        import: &I,
+        emit: &E,
+    ) -> Result<Option<u16>, Error>
+    where
+        I: MetadataImportTrait,
+        E: MetadataEmitTrait,
+    {
+        if self.instructions.is_empty() {
+            return Err(Error::InvalidCil);
+        }
+        let return_local = match return_type {
+            Type::Primitive(CorElementType::ELEMENT_TYPE_VOID) => None,
+            return_type => Some(self.add_locals(import, emit, slice::from_ref(return_type))?),
+        };
+        let try_start = self.label_at(0);
+
+        // The exit is labelled first, so labels handed out below can't clash with it
+        let exit = self.new_label();
+        let mut exit_code: Vec<Instruction> = return_local
+            .map(Instruction::load_local)
+            .into_iter()
+            .chain(Some(Instruction::new(RET, Operand::InlineNone)))
+            .collect();
+        exit_code[0].label = Some(exit);
+        let exit_length = exit_code.len();
+        self.instructions.append(&mut exit_code);
+
+        let mut index = 0;
+        while index < self.instructions.len() - exit_length {
+            let instruction = &mut self.instructions[index];
+            if instruction.opcode == TAILCALL {
+                instruction.opcode = NOP;
+            } else if instruction.opcode == RET {
+                let mut leave: Vec<Instruction> = return_local
+                    .map(Instruction::store_local)
+                    .into_iter()
+                    .chain(Some(Instruction::new(LEAVE_S, Operand::BrTarget(exit))))
+                    .collect();
+                // Branches to the `ret` now land on the code that replaces it
+                leave[0].label = instruction.label;
+                leave[0].original_offset = instruction.original_offset;
+                let length = leave.len();
+                self.instructions.splice(index..=index, leave);
+                index += length;
+                continue;
+            }
+            index += 1;
+        }
+        let body_end = self.instructions.len() - exit_length;
+        let try_last = self.label_at(body_end - 1);
+
+        epilogue.push(Instruction::new(ENDFINALLY, Operand::InlineNone));
+        let handler_length = epilogue.len();
+        self.insert(body_end, epilogue)?;
+        let handler_start = self.label_at(body_end);
+        let handler_last = self.label_at(body_end + handler_length - 1);
+
+        let clause = FatSectionClause {
+            is_exception: true,
+            is_filter: false,
+            is_finally: handler == WrapHandler::Finally,
+            is_fault: handler == WrapHandler::Fault,
+            try_offset: 0,
+            try_length: 0,
+            handler_offset: 0,
+            handler_length: 0,
+            class_token_or_filter_offset: 0,
+            labels: Some(ClauseLabels {
+                try_start,
+                try_last,
+                handler_start,
+                handler_last,
+                filter_start: None,
+            }),
+        };
+        // Enclosing clauses must come after the clauses they enclose
+        match self.sections.last_mut() {
+            Some(section) => {
+                section.expand();
+                if let Section::FatSection(_, clauses) = section {
+                    clauses.push(clause);
+                }
+                section.update_header(false);
+            }
+            None => {
+                let header = FatSectionHeader {
+                    is_eh_table: true,
+                    more_sects: false,
+                    data_size: 0,
+                };
+                let mut section = Section::FatSection(header, vec![clause]);
+                section.update_header(false);
+                self.sections.push(section);
+            }
+        }
+        Ok(return_local)
+    }
+}
@@ -0,0 +1,221 @@
+use crate::cil::{
+    flow::{self, ClauseRegions, Layout},
+    stack, Error, Method,
+};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// A problem found in a method body, pointing at the offending instruction
+/// index or at the clause index in the order clauses appear in the sections.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// The instruction reuses a label defined earlier.
+    DuplicateLabel {
+        index: usize,
+    },
+    /// The branch targets a missing label or the middle of an instruction.
+    InvalidBranchTarget {
+        index: usize,
+    },
+    /// Control reaches the instruction with different stack depths.
+    StackMismatch {
+        index: usize,
+        expected: usize,
+        found: usize,
+    },
+    StackUnderflow {
+        index: usize,
+    },
+    UnresolvedSignature {
+        index: usize,
+        token: u32,
+    },
+    InvalidSignature {
+        index: usize,
+    },
+    /// Control runs past the last instruction.
+    FallsOffEnd {
+        index: usize,
+    },
+    /// The clause regions do not start and end on instruction boundaries.
+    InvalidClause {
+        clause: usize,
+    },
+    /// The clause partially overlaps another one instead of nesting in it.
+    OverlappingClauses {
+        clause: usize,
+        other: usize,
+    },
+    /// Control leaves a protected region or handler without `leave`.
+    ExitWithoutLeave {
+        index: usize,
+    },
+    /// Control enters a protected region other than at its start, or a handler.
+    BranchIntoRegion {
+        index: usize,
+    },
+    /// Control enters a protected region or handler with items on the stack
+    /// other than the exception object (ECMA-335 III.1.7.5).
+    NonEmptyStackOnEntry {
+        index: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// Checks a method body before it is handed to the runtime. `signature`
+/// resolves call site tokens as in `Method::max_stack`.
+pub fn verify<F>(method: &Method, mut signature: F) -> Result<(), Vec<Diagnostic>>
+where
+    F: FnMut(u32) -> Option<Vec<u8>>,
+{
+    let instructions = &method.instructions;
+    let layout = match Layout::new(instructions) {
+        Ok(layout) => layout,
+        Err(_) => {
+            let mut labels = HashSet::new();
+            let index = instructions
+                .iter()
+                .position(|i| matches!(i.label, Some(label) if !labels.insert(label)))
+                .unwrap_or(0);
+            return Err(vec![Diagnostic::DuplicateLabel { index }]);
+        }
+    };
+    let mut diagnostics = Vec::new();
+    let mut regions = Vec::new();
+    for (clause, c) in flow::clauses(method).iter().enumerate() {
+        match ClauseRegions::new(c, &layout) {
+            Some(clause_regions) => regions.push(clause_regions),
+            None => diagnostics.push(Diagnostic::InvalidClause { clause }),
+        }
+    }
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    check_nesting(&regions, &mut diagnostics);
+
+    let mut pending = vec![(0, 0, 0)];
+    let mut entries = HashMap::new();
+    for clause_regions in regions.iter() {
+        let depth = !clause_regions.is_finally_or_fault as usize;
+        pending.push((clause_regions.handler.start, depth, 0));
+        entries.insert(clause_regions.try_.start, 0);
+        entries.insert(clause_regions.handler.start, depth);
+        if let Some(filter) = &clause_regions.filter {
+            pending.push((filter.start, 1, 0));
+            entries.insert(filter.start, 1);
+        }
+    }
+    let mut depths = vec![None; instructions.len()];
+    while let Some((index, depth, from)) = pending.pop() {
+        if index == instructions.len() {
+            diagnostics.push(Diagnostic::FallsOffEnd { index: from });
+            continue;
+        }
+        match entries.get(&index) {
+            Some(&expected) if expected != depth => {
+                diagnostics.push(Diagnostic::NonEmptyStackOnEntry {
+                    index,
+                    expected,
+                    found: depth,
+                });
+                continue;
+            }
+            _ => {}
+        }
+        match depths[index] {
+            Some(expected) if expected != depth => {
+                diagnostics.push(Diagnostic::StackMismatch {
+                    index,
+                    expected,
+                    found: depth,
+                });
+                continue;
+            }
+            Some(_) => continue,
+            None => depths[index] = Some(depth),
+        }
+        let instruction = &instructions[index];
+        let depth = match stack::stack_behavior(instruction, &mut signature) {
+            Ok((pops, pushes)) => match depth.checked_sub(pops) {
+                Some(depth) => depth + pushes,
+                None => {
+                    diagnostics.push(Diagnostic::StackUnderflow { index });
+                    continue;
+                }
+            },
+            Err(Error::UnresolvedSignature(token)) => {
+                diagnostics.push(Diagnostic::UnresolvedSignature { index, token });
+                continue;
+            }
+            Err(_) => {
+                diagnostics.push(Diagnostic::InvalidSignature { index });
+                continue;
+            }
+        };
+        let successors = match layout.successors(instructions, index) {
+            Ok(successors) => successors,
+            Err(_) => {
+                diagnostics.push(Diagnostic::InvalidBranchTarget { index });
+                continue;
+            }
+        };
+        let is_leave = flow::is_leave(instruction);
+        for successor in successors {
+            check_edge(&regions, index, successor, is_leave, &mut diagnostics);
+            pending.push((successor, if is_leave { 0 } else { depth }, index));
+        }
+    }
+
+    match diagnostics.is_empty() {
+        true => Ok(()),
+        false => Err(diagnostics),
+    }
+}
+
+/// Regions of different clauses are either disjoint or nested. Clauses may
+/// share the same protected region, e.g. a try with several catch handlers.
+fn check_nesting(regions: &[ClauseRegions], diagnostics: &mut Vec<Diagnostic>) {
+    let disjoint_or_nested = |a: &Range<usize>, b: &Range<usize>| {
+        a.end <= b.start
+            || b.end <= a.start
+            || (a.start <= b.start && b.end <= a.end)
+            || (b.start <= a.start && a.end <= b.end)
+    };
+    for (clause, a) in regions.iter().enumerate() {
+        for (other, b) in regions.iter().enumerate().skip(clause + 1) {
+            let nested = a
+                .all()
+                .iter()
+                .all(|a| b.all().iter().all(|b| disjoint_or_nested(a, b)));
+            if !nested {
+                diagnostics.push(Diagnostic::OverlappingClauses { clause, other });
+            }
+        }
+    }
+}
+
+/// Control may only enter a protected region at its start and never branch
+/// into a handler; it may only exit either with `leave`.
+fn check_edge(
+    regions: &[ClauseRegions],
+    from: usize,
+    to: usize,
+    is_leave: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for clause_regions in regions.iter() {
+        for region in clause_regions.all() {
+            let exits = region.contains(&from) && !region.contains(&to);
+            let enters = !region.contains(&from) && region.contains(&to);
+            if exits && !is_leave {
+                diagnostics.push(Diagnostic::ExitWithoutLeave { index: from });
+                return;
+            }
+            if enters && !(region == clause_regions.try_ && to == region.start) {
+                diagnostics.push(Diagnostic::BranchIntoRegion { index: from });
+                return;
+            }
+        }
+    }
+}
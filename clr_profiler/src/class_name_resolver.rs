@@ -0,0 +1,84 @@
+use crate::{
+    cil::{type_name, Error, NameStyle},
+    ffi::{ClassID, CorOpenFlags, E_FAIL, HRESULT, S_FALSE},
+    CorProfilerInfo2,
+};
+use std::collections::HashMap;
+
+/// Names classes the way C# writes them, e.g. `Dictionary<string, List<int>>[]`,
+/// or in another `NameStyle`, walking the element classes of arrays and the
+/// type arguments of generic instantiations. Names are cached per `ClassID`,
+/// call `class_unload_finished` from the callback of the same name so a reused
+/// `ClassID` isn't misnamed.
+#[derive(Clone)]
+pub struct ClassNameResolver<I: CorProfilerInfo2> {
+    info: I,
+    style: NameStyle,
+    names: HashMap<ClassID, String>,
+}
+impl<I: CorProfilerInfo2> ClassNameResolver<I> {
+    pub fn new(info: I) -> Self {
+        Self::with_style(info, NameStyle::CSharp)
+    }
+    pub fn with_style(info: I, style: NameStyle) -> Self {
+        ClassNameResolver {
+            info,
+            style,
+            names: HashMap::new(),
+        }
+    }
+    pub fn name(&mut self, class_id: ClassID) -> Result<String, HRESULT> {
+        if let Some(name) = self.names.get(&class_id) {
+            return Ok(name.clone());
+        }
+        // Ranks go outermost first, `int[][,]` holds two dimensional arrays
+        let mut ranks = String::new();
+        let mut element_class_id = class_id;
+        let element_name = loop {
+            match self.info.is_array_class(element_class_id) {
+                Ok(array) => {
+                    let commas = ",".repeat(array.rank.saturating_sub(1) as usize);
+                    ranks.push_str(&format!("[{}]", commas));
+                    match array.element_class_id {
+                        Some(id) if id != 0 => element_class_id = id,
+                        _ => {
+                            let name = self
+                                .style
+                                .primitive_name(array.element_type)
+                                .map(str::to_string)
+                                .unwrap_or_else(|| format!("{:?}", array.element_type));
+                            break name;
+                        }
+                    }
+                }
+                // Not an array
+                Err(S_FALSE) if element_class_id == class_id => break self.class_name(class_id)?,
+                Err(S_FALSE) => break self.name(element_class_id)?,
+                Err(hr) => return Err(hr),
+            }
+        };
+        let name = format!("{}{}", element_name, ranks);
+        self.names.insert(class_id, name.clone());
+        Ok(name)
+    }
+    /// Forgets the name of the class.
+    pub fn class_unload_finished(&mut self, class_id: ClassID) {
+        self.names.remove(&class_id);
+    }
+    fn class_name(&mut self, class_id: ClassID) -> Result<String, HRESULT> {
+        let class_info = self.info.get_class_id_info_2(class_id)?;
+        let metadata = self
+            .info
+            .get_module_metadata(class_info.module_id, CorOpenFlags::ofRead)?;
+        let full_name = type_name(&metadata, class_info.token).map_err(|error| match error {
+            Error::Metadata(hr) => hr,
+            _ => E_FAIL,
+        })?;
+        let type_args = class_info
+            .type_args
+            .iter()
+            .map(|type_arg| self.name(*type_arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.style.class_name(&full_name, &type_args))
+    }
+}
@@ -321,7 +321,7 @@ pub enum COR_PRF_RUNTIME_TYPE {
     COR_PRF_CORE_CLR = 2,
 }
 #[repr(C)]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CorElementType {
     ELEMENT_TYPE_END = 0x00,
     ELEMENT_TYPE_VOID = 0x01,
@@ -4,6 +4,7 @@ use crate::ffi::c_long;
 pub type HRESULT = c_long;
 
 pub const S_OK: HRESULT = 0;
+pub const S_FALSE: HRESULT = 1;
 
 pub const E_NOINTERFACE: HRESULT = 0x8000_4002;
 pub const E_OUTOFMEMORY: HRESULT = 0x8007_000E;
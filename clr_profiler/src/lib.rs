@@ -2,13 +2,17 @@
 extern crate bitflags;
 
 pub mod cil;
+mod class_name_resolver;
 pub mod ffi;
+mod metadata_emit;
 mod metadata_import;
 mod profiler_info;
 mod traits;
 mod types;
 
+pub use class_name_resolver::*;
 pub use clr_profiler_macros::*;
+pub use metadata_emit::*;
 pub use metadata_import::*;
 pub use profiler_info::*;
 pub use traits::*;
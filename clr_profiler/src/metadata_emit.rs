@@ -0,0 +1,38 @@
+use crate::{
+    ffi::{mdSignature, MetaDataEmit as FFIMetaDataEmit, HRESULT, S_OK},
+    MetadataEmitTrait,
+};
+use std::mem::MaybeUninit;
+
+#[derive(Clone)]
+pub struct MetadataEmit {
+    emit: *const FFIMetaDataEmit,
+}
+
+impl MetadataEmit {
+    pub fn new(metadata_emit: *const FFIMetaDataEmit) -> Self {
+        MetadataEmit {
+            emit: metadata_emit,
+        }
+    }
+    fn emit(&self) -> &FFIMetaDataEmit {
+        unsafe { self.emit.as_ref().unwrap() }
+    }
+}
+
+impl MetadataEmitTrait for MetadataEmit {
+    fn get_token_from_sig(&self, sig: &[u8]) -> Result<mdSignature, HRESULT> {
+        let mut md_sig = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.emit()
+                .GetTokenFromSig(sig.as_ptr(), sig.len() as u32, md_sig.as_mut_ptr())
+        };
+        match hr {
+            S_OK => {
+                let md_sig = unsafe { md_sig.assume_init() };
+                Ok(md_sig)
+            }
+            _ => Err(hr),
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use crate::{
     ffi::{
-        mdMethodDef, CorMethodAttr, CorMethodImpl, MetaDataImport as FFIMetaDataImport, HRESULT,
-        S_OK, WCHAR, mdTypeDef,
+        mdFieldDef, mdMemberRef, mdMethodDef, mdSignature, mdString, mdTypeRef, CorMethodAttr,
+        CorMethodImpl, MetaDataImport as FFIMetaDataImport, HRESULT, S_OK, WCHAR, mdTypeDef, E_FAIL,
+        mdGenericParam, mdToken, HCORENUM, S_FALSE, ULONG, mdTypeSpec, mdMethodSpec,
     },
-    MetadataImportTrait, MethodProps, TypeDefProps,
+    FieldProps, GenericParamProps, MemberRefProps, MetadataImportTrait, MethodProps,
+    MethodSpecProps, TypeDefProps, TypeRefProps,
 };
 use std::{mem::MaybeUninit, ptr};
 use widestring::U16CString;
@@ -22,6 +24,266 @@ impl MetadataImport {
     fn import(&self) -> &FFIMetaDataImport {
         unsafe { self.import.as_ref().unwrap() }
     }
+    fn enumerate<'a, F>(&'a self, fetch: F) -> MetadataEnum<'a>
+    where
+        F: FnMut(&FFIMetaDataImport, &mut HCORENUM, &mut [mdToken], &mut ULONG) -> HRESULT + 'a,
+    {
+        let import = self.import();
+        let mut fetch = fetch;
+        MetadataEnum::new(
+            move |enum_handle, page, count| fetch(import, enum_handle, page, count),
+            move |enum_handle| unsafe { import.CloseEnum(enum_handle) },
+        )
+    }
+    /// Type definitions of the module, nested ones included.
+    ///
+    /// ```
+    /// # use clr_profiler::{ffi::HRESULT, MetadataImport};
+    /// fn methods(metadata: &MetadataImport) -> Result<Vec<u32>, HRESULT> {
+    ///     let mut methods = Vec::new();
+    ///     for type_def in metadata.enum_type_defs() {
+    ///         for method in metadata.enum_methods(type_def?) {
+    ///             methods.push(method?);
+    ///         }
+    ///     }
+    ///     Ok(methods)
+    /// }
+    /// ```
+    pub fn enum_type_defs(&self) -> MetadataEnum<'_> {
+        self.enumerate(|import, enum_handle, page, count| unsafe {
+            import.EnumTypeDefs(enum_handle, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    pub fn enum_type_refs(&self) -> MetadataEnum<'_> {
+        self.enumerate(|import, enum_handle, page, count| unsafe {
+            import.EnumTypeRefs(enum_handle, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    pub fn enum_type_specs(&self) -> MetadataEnum<'_> {
+        self.enumerate(|import, enum_handle, page, count| unsafe {
+            import.EnumTypeSpecs(enum_handle, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    pub fn enum_module_refs(&self) -> MetadataEnum<'_> {
+        self.enumerate(|import, enum_handle, page, count| unsafe {
+            import.EnumModuleRefs(enum_handle, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    pub fn enum_signatures(&self) -> MetadataEnum<'_> {
+        self.enumerate(|import, enum_handle, page, count| unsafe {
+            import.EnumSignatures(enum_handle, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    pub fn enum_user_strings(&self) -> MetadataEnum<'_> {
+        self.enumerate(|import, enum_handle, page, count| unsafe {
+            import.EnumUserStrings(enum_handle, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    pub fn enum_interface_impls(&self, td: mdTypeDef) -> MetadataEnum<'_> {
+        self.enumerate(move |import, enum_handle, page, count| unsafe {
+            import.EnumInterfaceImpls(
+                enum_handle,
+                td,
+                page.as_mut_ptr(),
+                page.len() as ULONG,
+                count,
+            )
+        })
+    }
+    /// Methods and fields of the type, not the ones it inherits.
+    pub fn enum_members(&self, cl: mdTypeDef) -> MetadataEnum<'_> {
+        self.enumerate(move |import, enum_handle, page, count| unsafe {
+            import.EnumMembers(enum_handle, cl, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    pub fn enum_members_with_name(&self, cl: mdTypeDef, name: &str) -> MetadataEnum<'_> {
+        match U16CString::from_str(name) {
+            Ok(name) => self.enumerate(move |import, enum_handle, page, count| unsafe {
+                import.EnumMembersWithName(
+                    enum_handle,
+                    cl,
+                    name.as_ptr(),
+                    page.as_mut_ptr(),
+                    page.len() as ULONG,
+                    count,
+                )
+            }),
+            // No member is named with a nul character
+            Err(_) => MetadataEnum::empty(),
+        }
+    }
+    pub fn enum_methods(&self, cl: mdTypeDef) -> MetadataEnum<'_> {
+        self.enumerate(move |import, enum_handle, page, count| unsafe {
+            import.EnumMethods(enum_handle, cl, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    /// Methods of the type with this name, e.g. every overload of it.
+    pub fn enum_methods_with_name(&self, cl: mdTypeDef, name: &str) -> MetadataEnum<'_> {
+        match U16CString::from_str(name) {
+            Ok(name) => self.enumerate(move |import, enum_handle, page, count| unsafe {
+                import.EnumMethodsWithName(
+                    enum_handle,
+                    cl,
+                    name.as_ptr(),
+                    page.as_mut_ptr(),
+                    page.len() as ULONG,
+                    count,
+                )
+            }),
+            Err(_) => MetadataEnum::empty(),
+        }
+    }
+    pub fn enum_fields(&self, cl: mdTypeDef) -> MetadataEnum<'_> {
+        self.enumerate(move |import, enum_handle, page, count| unsafe {
+            import.EnumFields(enum_handle, cl, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    pub fn enum_fields_with_name(&self, cl: mdTypeDef, name: &str) -> MetadataEnum<'_> {
+        match U16CString::from_str(name) {
+            Ok(name) => self.enumerate(move |import, enum_handle, page, count| unsafe {
+                import.EnumFieldsWithName(
+                    enum_handle,
+                    cl,
+                    name.as_ptr(),
+                    page.as_mut_ptr(),
+                    page.len() as ULONG,
+                    count,
+                )
+            }),
+            Err(_) => MetadataEnum::empty(),
+        }
+    }
+    pub fn enum_params(&self, mb: mdMethodDef) -> MetadataEnum<'_> {
+        self.enumerate(move |import, enum_handle, page, count| unsafe {
+            import.EnumParams(enum_handle, mb, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    /// Member references whose parent is `tk_parent`, e.g. a type reference.
+    pub fn enum_member_refs(&self, tk_parent: mdToken) -> MetadataEnum<'_> {
+        self.enumerate(move |import, enum_handle, page, count| unsafe {
+            import.EnumMemberRefs(
+                enum_handle,
+                tk_parent,
+                page.as_mut_ptr(),
+                page.len() as ULONG,
+                count,
+            )
+        })
+    }
+    pub fn enum_properties(&self, td: mdTypeDef) -> MetadataEnum<'_> {
+        self.enumerate(move |import, enum_handle, page, count| unsafe {
+            import.EnumProperties(enum_handle, td, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    pub fn enum_events(&self, td: mdTypeDef) -> MetadataEnum<'_> {
+        self.enumerate(move |import, enum_handle, page, count| unsafe {
+            import.EnumEvents(enum_handle, td, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+    }
+    /// Custom attributes of `tk`, only the ones of type `tk_type` unless it's 0.
+    pub fn enum_custom_attributes(&self, tk: mdToken, tk_type: mdToken) -> MetadataEnum<'_> {
+        self.enumerate(move |import, enum_handle, page, count| unsafe {
+            import.EnumCustomAttributes(
+                enum_handle,
+                tk,
+                tk_type,
+                page.as_mut_ptr(),
+                page.len() as ULONG,
+                count,
+            )
+        })
+    }
+}
+
+type FetchPage<'a> = Box<dyn FnMut(&mut HCORENUM, &mut [mdToken], &mut ULONG) -> HRESULT + 'a>;
+type CloseEnum<'a> = Box<dyn FnMut(HCORENUM) + 'a>;
+
+/// Tokens of a metadata enumeration, fetched a page at a time. The `HCORENUM`
+/// is closed when the iterator is dropped, so stopping early doesn't leak it.
+///
+/// A failing fetch is yielded as an error and ends the iteration.
+pub struct MetadataEnum<'a> {
+    enum_handle: HCORENUM,
+    fetch: Option<FetchPage<'a>>,
+    close: Option<CloseEnum<'a>>,
+    page: Vec<mdToken>,
+    position: usize,
+}
+impl<'a> MetadataEnum<'a> {
+    pub const PAGE_SIZE: usize = 64;
+
+    /// Enumerates with `fetch`, an `Enum*` call of `IMetaDataImport` that
+    /// fills the page and sets the count. `close` is given the `HCORENUM` on
+    /// drop, once, if `fetch` opened one.
+    pub fn new<F, C>(fetch: F, close: C) -> Self
+    where
+        F: FnMut(&mut HCORENUM, &mut [mdToken], &mut ULONG) -> HRESULT + 'a,
+        C: FnMut(HCORENUM) + 'a,
+    {
+        MetadataEnum {
+            enum_handle: ptr::null(),
+            fetch: Some(Box::new(fetch)),
+            close: Some(Box::new(close)),
+            page: Vec::new(),
+            position: 0,
+        }
+    }
+    fn empty() -> Self {
+        MetadataEnum {
+            enum_handle: ptr::null(),
+            fetch: None,
+            close: None,
+            page: Vec::new(),
+            position: 0,
+        }
+    }
+}
+impl<'a> Iterator for MetadataEnum<'a> {
+    type Item = Result<mdToken, HRESULT>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position == self.page.len() {
+            let fetch = self.fetch.as_mut()?;
+            self.page.resize(Self::PAGE_SIZE, 0);
+            self.position = 0;
+            let mut count = 0;
+            let hr = fetch(&mut self.enum_handle, &mut self.page, &mut count);
+            self.page.truncate(count as usize);
+            match hr {
+                S_OK if count > 0 => (),
+                // No more tokens
+                S_OK | S_FALSE => {
+                    self.fetch = None;
+                    self.page.clear();
+                    return None;
+                }
+                _ => {
+                    self.fetch = None;
+                    self.page.clear();
+                    return Some(Err(hr));
+                }
+            }
+        }
+        let token = self.page[self.position];
+        self.position += 1;
+        Some(Ok(token))
+    }
+}
+impl<'a> Drop for MetadataEnum<'a> {
+    fn drop(&mut self) {
+        if let Some(close) = self.close.as_mut() {
+            if !self.enum_handle.is_null() {
+                close(self.enum_handle);
+            }
+        }
+    }
+}
+
+/// Name written by a metadata call into a buffer it sized, nul included.
+fn name_from_buffer(name_buffer: Vec<WCHAR>) -> Result<String, HRESULT> {
+    U16CString::from_vec_with_nul(name_buffer)
+        .map(|name| name.to_string_lossy())
+        .or(Err(E_FAIL))
 }
 
 impl MetadataImportTrait for MetadataImport {
@@ -137,4 +399,315 @@ impl MetadataImportTrait for MetadataImport {
             _ => Err(hr)
         }
     }
+
+    fn get_typeref_props(&self, tr: mdTypeRef) -> Result<TypeRefProps, HRESULT> {
+        let mut name_buffer_length = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import().GetTypeRefProps(
+                tr,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+                name_buffer_length.as_mut_ptr(),
+            )
+        };
+        if hr != S_OK {
+            return Err(hr);
+        }
+        let name_buffer_length = unsafe { name_buffer_length.assume_init() };
+        let mut name_buffer: Vec<WCHAR> = vec![0; name_buffer_length as usize];
+        let mut name_length = MaybeUninit::uninit();
+        let mut resolution_scope = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import().GetTypeRefProps(
+                tr,
+                resolution_scope.as_mut_ptr(),
+                name_buffer.as_mut_ptr(),
+                name_buffer_length,
+                name_length.as_mut_ptr(),
+            )
+        };
+        match hr {
+            S_OK => {
+                let name = name_from_buffer(name_buffer)?;
+                let resolution_scope = unsafe { resolution_scope.assume_init() };
+                Ok(TypeRefProps {
+                    name,
+                    resolution_scope,
+                })
+            }
+            _ => Err(hr),
+        }
+    }
+
+    fn get_member_ref_props(&self, mr: mdMemberRef) -> Result<MemberRefProps, HRESULT> {
+        let mut name_buffer_length = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import().GetMemberRefProps(
+                mr,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+                name_buffer_length.as_mut_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if hr != S_OK {
+            return Err(hr);
+        }
+        let name_buffer_length = unsafe { name_buffer_length.assume_init() };
+        let mut name_buffer: Vec<WCHAR> = vec![0; name_buffer_length as usize];
+        let mut name_length = MaybeUninit::uninit();
+        let mut parent_token = MaybeUninit::uninit();
+        let mut sig = MaybeUninit::uninit();
+        let mut sig_length = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import().GetMemberRefProps(
+                mr,
+                parent_token.as_mut_ptr(),
+                name_buffer.as_mut_ptr(),
+                name_buffer_length,
+                name_length.as_mut_ptr(),
+                sig.as_mut_ptr(),
+                sig_length.as_mut_ptr(),
+            )
+        };
+        match hr {
+            S_OK => {
+                let name = name_from_buffer(name_buffer)?;
+                let parent_token = unsafe { parent_token.assume_init() };
+                let sig = unsafe { sig.assume_init() };
+                let sig_length = unsafe { sig_length.assume_init() };
+                Ok(MemberRefProps {
+                    parent_token,
+                    name,
+                    sig,
+                    sig_length,
+                })
+            }
+            _ => Err(hr),
+        }
+    }
+
+    fn get_field_props(&self, fd: mdFieldDef) -> Result<FieldProps, HRESULT> {
+        let mut name_buffer_length = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import().GetFieldProps(
+                fd,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+                name_buffer_length.as_mut_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if hr != S_OK {
+            return Err(hr);
+        }
+        let name_buffer_length = unsafe { name_buffer_length.assume_init() };
+        let mut name_buffer: Vec<WCHAR> = vec![0; name_buffer_length as usize];
+        let mut name_length = MaybeUninit::uninit();
+        let mut class_token = MaybeUninit::uninit();
+        let mut attr_flags = MaybeUninit::uninit();
+        let mut sig = MaybeUninit::uninit();
+        let mut sig_length = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import().GetFieldProps(
+                fd,
+                class_token.as_mut_ptr(),
+                name_buffer.as_mut_ptr(),
+                name_buffer_length,
+                name_length.as_mut_ptr(),
+                attr_flags.as_mut_ptr(),
+                sig.as_mut_ptr(),
+                sig_length.as_mut_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        match hr {
+            S_OK => {
+                let name = name_from_buffer(name_buffer)?;
+                let class_token = unsafe { class_token.assume_init() };
+                let attr_flags = unsafe { attr_flags.assume_init() };
+                let sig = unsafe { sig.assume_init() };
+                let sig_length = unsafe { sig_length.assume_init() };
+                Ok(FieldProps {
+                    class_token,
+                    name,
+                    attr_flags,
+                    sig,
+                    sig_length,
+                })
+            }
+            _ => Err(hr),
+        }
+    }
+
+    fn get_user_string(&self, stk: mdString) -> Result<String, HRESULT> {
+        let mut string_buffer_length = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import().GetUserString(
+                stk,
+                ptr::null_mut(),
+                0,
+                string_buffer_length.as_mut_ptr(),
+            )
+        };
+        if hr != S_OK {
+            return Err(hr);
+        }
+        let string_buffer_length = unsafe { string_buffer_length.assume_init() };
+        let mut string_buffer: Vec<WCHAR> = vec![0; string_buffer_length as usize];
+        let mut string_length = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import().GetUserString(
+                stk,
+                string_buffer.as_mut_ptr(),
+                string_buffer_length,
+                string_length.as_mut_ptr(),
+            )
+        };
+        match hr {
+            // User strings are not null terminated
+            S_OK => Ok(String::from_utf16_lossy(&string_buffer)),
+            _ => Err(hr),
+        }
+    }
+    fn get_sig_from_token(&self, md_sig: mdSignature) -> Result<Vec<u8>, HRESULT> {
+        let mut sig = MaybeUninit::uninit();
+        let mut sig_length = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import()
+                .GetSigFromToken(md_sig, sig.as_mut_ptr(), sig_length.as_mut_ptr())
+        };
+        match hr {
+            S_OK => {
+                let sig = unsafe { sig.assume_init() };
+                let sig_length = unsafe { sig_length.assume_init() };
+                // The blob is owned by the metadata, copy it out
+                let sig = unsafe { std::slice::from_raw_parts(sig, sig_length as usize) };
+                Ok(sig.to_vec())
+            }
+            _ => Err(hr),
+        }
+    }
+    fn get_type_spec_from_token(&self, typespec: mdTypeSpec) -> Result<Vec<u8>, HRESULT> {
+        let mut sig = MaybeUninit::uninit();
+        let mut sig_length = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import()
+                .GetTypeSpecFromToken(typespec, sig.as_mut_ptr(), sig_length.as_mut_ptr())
+        };
+        match hr {
+            S_OK => {
+                let sig = unsafe { sig.assume_init() };
+                let sig_length = unsafe { sig_length.assume_init() };
+                let sig = unsafe { std::slice::from_raw_parts(sig, sig_length as usize) };
+                Ok(sig.to_vec())
+            }
+            _ => Err(hr),
+        }
+    }
+    fn get_method_spec_props(&self, mi: mdMethodSpec) -> Result<MethodSpecProps, HRESULT> {
+        let mut parent = MaybeUninit::uninit();
+        let mut sig = MaybeUninit::uninit();
+        let mut sig_length = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import().GetMethodSpecProps(
+                mi,
+                parent.as_mut_ptr(),
+                sig.as_mut_ptr(),
+                sig_length.as_mut_ptr(),
+            )
+        };
+        match hr {
+            S_OK => {
+                let parent = unsafe { parent.assume_init() };
+                let sig = unsafe { sig.assume_init() };
+                let sig_length = unsafe { sig_length.assume_init() };
+                let sig = unsafe { std::slice::from_raw_parts(sig, sig_length as usize) };
+                Ok(MethodSpecProps {
+                    parent,
+                    sig: sig.to_vec(),
+                })
+            }
+            _ => Err(hr),
+        }
+    }
+    fn get_nested_class_props(&self, td_nested_class: mdTypeDef) -> Result<mdTypeDef, HRESULT> {
+        let mut enclosing_class = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import()
+                .GetNestedClassProps(td_nested_class, enclosing_class.as_mut_ptr())
+        };
+        match hr {
+            S_OK => Ok(unsafe { enclosing_class.assume_init() }),
+            _ => Err(hr),
+        }
+    }
+    fn enum_generic_params(&self, tk: mdToken) -> Result<Vec<mdGenericParam>, HRESULT> {
+        self.enumerate(move |import, enum_handle, page, count| unsafe {
+            import.EnumGenericParams(enum_handle, tk, page.as_mut_ptr(), page.len() as ULONG, count)
+        })
+        .collect()
+    }
+    fn get_generic_param_props(&self, gp: mdGenericParam) -> Result<GenericParamProps, HRESULT> {
+        let mut name_buffer_length = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import().GetGenericParamProps(
+                gp,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+                name_buffer_length.as_mut_ptr(),
+            )
+        };
+        if hr != S_OK {
+            return Err(hr);
+        }
+        let name_buffer_length = unsafe { name_buffer_length.assume_init() };
+        let mut name_buffer: Vec<WCHAR> = vec![0; name_buffer_length as usize];
+        let mut name_length = MaybeUninit::uninit();
+        let mut seq = MaybeUninit::uninit();
+        let mut flags = MaybeUninit::uninit();
+        let mut owner = MaybeUninit::uninit();
+        let hr = unsafe {
+            self.import().GetGenericParamProps(
+                gp,
+                seq.as_mut_ptr(),
+                flags.as_mut_ptr(),
+                owner.as_mut_ptr(),
+                ptr::null_mut(),
+                name_buffer.as_mut_ptr(),
+                name_buffer_length,
+                name_length.as_mut_ptr(),
+            )
+        };
+        match hr {
+            S_OK => {
+                let name = name_from_buffer(name_buffer)?;
+                let seq = unsafe { seq.assume_init() };
+                let flags = unsafe { flags.assume_init() };
+                let owner = unsafe { owner.assume_init() };
+                Ok(GenericParamProps {
+                    seq,
+                    flags,
+                    owner,
+                    name,
+                })
+            }
+            _ => Err(hr),
+        }
+    }
 }
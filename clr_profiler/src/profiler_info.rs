@@ -6,7 +6,7 @@ use crate::{
         FunctionEnter, FunctionEnter2, FunctionEnter3, FunctionEnter3WithInfo, FunctionID,
         FunctionIDMapper, FunctionIDMapper2, FunctionLeave, FunctionLeave2, FunctionLeave3,
         FunctionLeave3WithInfo, FunctionTailcall, FunctionTailcall2, FunctionTailcall3,
-        FunctionTailcall3WithInfo, IMetaDataImport2, MethodMalloc, ModuleID, ObjectID,
+        FunctionTailcall3WithInfo, IMetaDataEmit2, IMetaDataImport2, MethodMalloc, ModuleID, ObjectID,
         ObjectReferenceCallback, ReJITID, StackSnapshotCallback, ThreadID, BOOL, BYTE,
         COR_DEBUG_IL_TO_NATIVE_MAP, COR_FIELD_OFFSET, COR_IL_MAP, COR_PRF_CODE_INFO,
         COR_PRF_ELT_INFO, COR_PRF_EX_CLAUSE_INFO, COR_PRF_FRAME_INFO, COR_PRF_GC_GENERATION_RANGE,
@@ -19,8 +19,8 @@ use crate::{
     CorProfilerInfo4, CorProfilerInfo5, CorProfilerInfo6, CorProfilerInfo7, CorProfilerInfo8,
     CorProfilerInfo9, DynamicFunctionInfo, EnumNgenModuleMethodsInliningThisMethod, EventMask2,
     FunctionAndRejit, FunctionEnter3Info, FunctionInfo, FunctionInfo2, FunctionLeave3Info,
-    FunctionTokenAndMetadata, IlFunctionBody, MetadataImport, ModuleInfo, ModuleInfo2, RuntimeInfo,
-    StringLayout,
+    FunctionTokenAndMetadata, IlFunctionBody, MetadataEmit, MetadataImport, ModuleInfo,
+    ModuleInfo2, RuntimeInfo, StringLayout,
 };
 use std::{mem::MaybeUninit, ptr};
 use uuid::Uuid;
@@ -325,6 +325,33 @@ impl CorProfilerInfo for ProfilerInfo {
             _ => Err(hr),
         }
     }
+    fn get_module_metadata_emit(
+        &self,
+        module_id: ModuleID,
+        open_flags: CorOpenFlags,
+    ) -> Result<MetadataEmit, HRESULT> {
+        let mut metadata_emit = MaybeUninit::uninit();
+        let open_flags = open_flags.bits();
+        let riid = IMetaDataEmit2::IID;
+        let hr = unsafe {
+            self.info().GetModuleMetaData(
+                module_id,
+                open_flags,
+                &riid,
+                metadata_emit.as_mut_ptr(),
+            )
+        };
+
+        match hr {
+            S_OK => {
+                // The same object implements IMetaDataEmit2 when asked for its IID
+                let metadata_emit = unsafe { metadata_emit.assume_init() } as *const _;
+                let metadata_emit = MetadataEmit::new(metadata_emit);
+                Ok(metadata_emit)
+            }
+            _ => Err(hr),
+        }
+    }
     fn get_il_function_body(
         &self,
         module_id: ModuleID,
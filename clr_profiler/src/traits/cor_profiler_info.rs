@@ -6,7 +6,7 @@ use crate::{
         HRESULT, LPCBYTE,
     },
     AppDomainInfo, ArrayClassInfo, AssemblyInfo, ClassInfo, FunctionInfo, FunctionTokenAndMetadata,
-    IlFunctionBody, MetadataImport, ModuleInfo,
+    IlFunctionBody, MetadataEmit, MetadataImport, ModuleInfo,
 };
 
 pub trait CorProfilerInfo {
@@ -37,6 +37,11 @@ pub trait CorProfilerInfo {
         module_id: ModuleID,
         open_flags: CorOpenFlags,
     ) -> Result<MetadataImport, HRESULT>;
+    fn get_module_metadata_emit(
+        &self,
+        module_id: ModuleID,
+        open_flags: CorOpenFlags,
+    ) -> Result<MetadataEmit, HRESULT>;
     fn get_il_function_body(
         &self,
         module_id: ModuleID,
@@ -0,0 +1,5 @@
+use crate::ffi::{mdSignature, HRESULT};
+
+pub trait MetadataEmitTrait {
+    fn get_token_from_sig(&self, sig: &[u8]) -> Result<mdSignature, HRESULT>;
+}
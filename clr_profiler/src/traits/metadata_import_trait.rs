@@ -1,9 +1,23 @@
 use crate::{
-    ffi::{mdMethodDef, HRESULT, mdTypeDef},
-    MethodProps, TypeDefProps,
+    ffi::{
+        mdFieldDef, mdMemberRef, mdMethodDef, mdSignature, mdString, mdTypeRef, HRESULT, mdTypeDef,
+        mdGenericParam, mdToken, mdTypeSpec, mdMethodSpec,
+    },
+    FieldProps, GenericParamProps, MemberRefProps, MethodProps, MethodSpecProps, TypeDefProps,
+    TypeRefProps,
 };
 
 pub trait MetadataImportTrait {
     fn get_method_props(&self, mb: mdMethodDef) -> Result<MethodProps, HRESULT>;
     fn get_typedef_props(&self, mb: mdTypeDef) -> Result<TypeDefProps, HRESULT>;
+    fn get_typeref_props(&self, tr: mdTypeRef) -> Result<TypeRefProps, HRESULT>;
+    fn get_member_ref_props(&self, mr: mdMemberRef) -> Result<MemberRefProps, HRESULT>;
+    fn get_field_props(&self, fd: mdFieldDef) -> Result<FieldProps, HRESULT>;
+    fn get_user_string(&self, stk: mdString) -> Result<String, HRESULT>;
+    fn get_sig_from_token(&self, md_sig: mdSignature) -> Result<Vec<u8>, HRESULT>;
+    fn get_nested_class_props(&self, td_nested_class: mdTypeDef) -> Result<mdTypeDef, HRESULT>;
+    fn enum_generic_params(&self, tk: mdToken) -> Result<Vec<mdGenericParam>, HRESULT>;
+    fn get_generic_param_props(&self, gp: mdGenericParam) -> Result<GenericParamProps, HRESULT>;
+    fn get_type_spec_from_token(&self, typespec: mdTypeSpec) -> Result<Vec<u8>, HRESULT>;
+    fn get_method_spec_props(&self, mi: mdMethodSpec) -> Result<MethodSpecProps, HRESULT>;
 }
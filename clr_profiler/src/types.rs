@@ -125,3 +125,33 @@ pub struct TypeDefProps {
     pub attr_flags: DWORD, // TODO: CorTypeAttr
     pub base_type: mdToken
 }
+pub struct TypeRefProps {
+    pub name: String,
+    pub resolution_scope: mdToken,
+}
+pub struct MemberRefProps {
+    pub parent_token: mdToken,
+    pub name: String,
+    pub sig: PCCOR_SIGNATURE,
+    pub sig_length: u32,
+}
+pub struct GenericParamProps {
+    /// Index of the parameter in the list of its owner.
+    pub seq: u32,
+    pub flags: DWORD,
+    pub owner: mdToken,
+    pub name: String,
+}
+pub struct MethodSpecProps {
+    /// Generic method definition or reference being instantiated.
+    pub parent: mdToken,
+    /// Blob holding the type arguments.
+    pub sig: Vec<u8>,
+}
+pub struct FieldProps {
+    pub class_token: mdTypeDef,
+    pub name: String,
+    pub attr_flags: DWORD,
+    pub sig: PCCOR_SIGNATURE,
+    pub sig_length: u32,
+}
@@ -0,0 +1,49 @@
+mod fixtures;
+
+use clr_profiler::cil::{
+    assemble, assemble_method, call, disassemble, ldarg_0, ldc_i4_s, ret, Error, Instruction,
+    Label, Operand, Token, BRFALSE_S,
+};
+use fixtures::{parse, TRY_CATCH_METHOD};
+
+#[test]
+fn given_il_text_when_assembling_then_labels_become_branch_targets() {
+    let instructions = assemble(
+        "      ldarg.0
+      brfalse.s skip // forward reference
+      ldc.i4.s -2
+      call token(0x0A000012)
+skip: ret",
+    )
+    .unwrap();
+
+    assert_eq!(
+        instructions,
+        vec![
+            ldarg_0(),
+            Instruction::new(BRFALSE_S, Operand::BrTarget(Label(0))),
+            ldc_i4_s(0xFE),
+            call(Token::member_ref(0x0A00_0012).unwrap()).unwrap(),
+            ret().with_label(Label(0)),
+        ]
+    );
+}
+
+#[test]
+fn given_disassembled_method_when_assembling_then_same_body_is_produced() {
+    let method = parse(&TRY_CATCH_METHOD);
+
+    let assembled = assemble_method(&disassemble(&method, |_| None)).unwrap();
+
+    assert_eq!(assembled.into_bytes(), method.into_bytes());
+}
+
+#[test]
+fn given_unknown_mnemonic_when_assembling_then_line_is_reported() {
+    let result = assemble("nop\nfoo.bar 1");
+
+    assert_eq!(
+        result,
+        Err(Error::Syntax(2, "unknown opcode `foo.bar`".to_string()))
+    );
+}
@@ -0,0 +1,62 @@
+mod fixtures;
+
+use clr_profiler::cil::{
+    ldarg_0, ldc_i4_1, nop, ret, ControlFlowGraph, Edge, EdgeKind, Instruction, Label, Loop,
+    Operand, BRTRUE_S,
+};
+use fixtures::{parse, BRANCHING_METHOD, TINY_METHOD, TRY_CATCH_METHOD};
+
+#[test]
+fn given_conditional_branch_when_building_cfg_then_both_paths_get_a_block() {
+    let method = parse(&BRANCHING_METHOD);
+
+    let cfg = ControlFlowGraph::new(&method).unwrap();
+
+    let ranges: Vec<_> = cfg.blocks.iter().map(|b| b.instructions.clone()).collect();
+    assert_eq!(ranges, vec![0..2, 2..4, 4..6]);
+    let successors: Vec<_> = cfg.blocks[0].successors.iter().map(|e| e.block).collect();
+    assert_eq!(successors, vec![1, 2]);
+    assert_eq!(cfg.dominators(), vec![None, Some(0), Some(0)]);
+}
+
+#[test]
+fn given_try_catch_when_building_cfg_then_handler_is_reached_through_exceptional_edge() {
+    let method = parse(&TRY_CATCH_METHOD);
+
+    let cfg = ControlFlowGraph::new(&method).unwrap();
+
+    assert_eq!(cfg.blocks[1].instructions, 2..4);
+    assert_eq!(
+        cfg.blocks[1].predecessors,
+        vec![Edge {
+            block: 0,
+            kind: EdgeKind::Exceptional
+        }]
+    );
+    assert!(cfg.dominates(0, 3));
+    assert!(cfg.blocks[2].predecessors.is_empty());
+}
+
+#[test]
+fn given_backward_branch_when_finding_loops_then_natural_loop_is_reported() {
+    let mut method = parse(&TINY_METHOD);
+    let head = Label(0x10);
+    method.instructions = vec![
+        ldc_i4_1(),
+        nop().with_label(head),
+        ldarg_0(),
+        Instruction::new(BRTRUE_S, Operand::BrTarget(head)),
+        ret(),
+    ];
+
+    let cfg = ControlFlowGraph::new(&method).unwrap();
+
+    assert_eq!(
+        cfg.loops(),
+        vec![Loop {
+            header: 1,
+            latches: vec![1],
+            blocks: vec![1],
+        }]
+    );
+}
@@ -0,0 +1,102 @@
+mod fixtures;
+
+use clr_profiler::cil::{
+    call, disassemble, token_name, type_name, ClauseKind, ExceptionClause, Token,
+};
+use clr_profiler::il;
+use fixtures::{parse, FakeMetadata, BRANCHING_METHOD, TINY_METHOD, TRY_CATCH_METHOD};
+
+#[test]
+fn given_generic_tokens_when_disassembling_with_metadata_then_specs_are_decoded() {
+    let list = Token::type_spec(0x1B00_0001).unwrap();
+    let method_spec = Token::method_spec(0x2B00_0001).unwrap();
+    let mut method = parse(&TINY_METHOD);
+    method
+        .insert_prelude(il! {
+                call { method_spec };
+                leave.s done;
+                pop;
+                leave.s done;
+            done:
+                newobj 0x0A000001;
+                isinst { list };
+                pop;
+        })
+        .unwrap();
+    method
+        .add_exception_clause(ExceptionClause {
+            kind: ClauseKind::Catch(list),
+            try_range: 0..2,
+            handler_range: 2..4,
+        })
+        .unwrap();
+
+    let text = disassemble(&method, |token| token_name(&FakeMetadata::default(), token));
+
+    assert_eq!(
+        text,
+        ".maxstack 8
+.try
+{
+  IL_0000: call Namespace.Outer`1+Inner::Method<string[]>
+  IL_0005: leave.s IL_000a
+}
+catch System.Collections.Generic.List<!0>
+{
+  IL_0007: pop
+  IL_0008: leave.s IL_000a
+}
+IL_000a: newobj token(0x0A000001)
+IL_000f: isinst System.Collections.Generic.List<!0>
+IL_0014: pop
+IL_0015: nop
+IL_0016: ret
+"
+    );
+    let metadata = FakeMetadata::default();
+    assert_eq!(
+        token_name(&metadata, 0x0200_0003),
+        type_name(&metadata, 0x0200_0003).ok()
+    );
+}
+
+#[test]
+fn given_try_catch_when_disassembling_then_clauses_render_as_blocks() {
+    let method = parse(&TRY_CATCH_METHOD);
+
+    let text = disassemble(&method, |token| match token {
+        0x0100_0001 => Some("System.Exception".to_string()),
+        _ => None,
+    });
+
+    assert_eq!(
+        text,
+        ".maxstack 1
+.try
+{
+  IL_0000: nop
+  IL_0001: leave.s IL_0007
+}
+catch System.Exception
+{
+  IL_0003: pop
+  IL_0004: leave.s IL_0007
+}
+IL_0006: nop
+IL_0007: ret
+"
+    );
+}
+
+#[test]
+fn given_unresolved_tokens_when_disassembling_then_raw_tokens_are_shown() {
+    let mut method = parse(&BRANCHING_METHOD);
+    method
+        .insert_prelude(vec![call(Token::member_ref(0x0A00_0001).unwrap()).unwrap()])
+        .unwrap();
+
+    let text = method.to_string();
+
+    assert!(text.contains("IL_0000: call token(0x0A000001)\n"));
+    assert!(text.contains("IL_0006: brfalse.s IL_000a\n"));
+}
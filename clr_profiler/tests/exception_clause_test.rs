@@ -0,0 +1,104 @@
+mod fixtures;
+
+use clr_profiler::cil::{nop, verify, ClauseKind, Error, ExceptionClause, Section, Token};
+use clr_profiler::il;
+use fixtures::{parse, TINY_METHOD, TWO_SECTIONS_METHOD};
+
+#[test]
+fn given_clauses_in_two_sections_when_adding_enclosing_clause_then_it_comes_after_both() {
+    let mut method = parse(&TWO_SECTIONS_METHOD);
+    let existing = method.exception_clauses().unwrap();
+
+    let clause = ExceptionClause {
+        kind: ClauseKind::Fault,
+        try_range: 0..4,
+        handler_range: 4..5,
+    };
+    method.add_exception_clause(clause.clone()).unwrap();
+
+    let mut expected = existing.clone();
+    expected.push(clause);
+    assert_eq!(method.exception_clauses(), Ok(expected));
+    match &method.sections[..] {
+        [Section::SmallSection(small, first), Section::FatSection(fat, second)] => {
+            assert!(small.more_sects);
+            assert!(!fat.more_sects);
+            assert_eq!((first.len(), second.len()), (1, 2));
+        }
+        sections => panic!("unexpected sections {:?}", sections),
+    }
+
+    // Enclosed by every other clause, so it goes first
+    let inner = ExceptionClause {
+        kind: ClauseKind::Catch(Token::type_ref(0x0100_0002).unwrap()),
+        try_range: 0..1,
+        handler_range: 1..2,
+    };
+    method.add_exception_clause(inner.clone()).unwrap();
+    assert_eq!(method.exception_clauses().unwrap()[0], inner);
+
+    let not_a_type = ClauseKind::Catch(Token::method_def(0x0600_0001).unwrap());
+    assert_eq!(
+        method.add_exception_clause(ExceptionClause {
+            kind: not_a_type,
+            ..existing[0].clone()
+        }),
+        Err(Error::InvalidExceptionClause)
+    );
+}
+
+#[test]
+fn given_injected_call_when_adding_catch_clause_then_it_is_protected() {
+    let mut method = parse(&TINY_METHOD);
+    method
+        .insert_prelude(il! {
+                call 0x0A00_0001;
+                leave.s done;
+                pop;
+                leave.s done;
+            done:
+                nop;
+        })
+        .unwrap();
+
+    let clause = ExceptionClause {
+        kind: ClauseKind::Catch(Token::type_ref(0x0100_0001).unwrap()),
+        try_range: 0..2,
+        handler_range: 2..4,
+    };
+    method.add_exception_clause(clause.clone()).unwrap();
+
+    assert_eq!(method.exception_clauses(), Ok(vec![clause]));
+    assert!(matches!(method.sections[..], [Section::SmallSection(_, _)]));
+    assert_eq!(verify(&method, |_| Some(vec![0x00, 0x00, 0x01])), Ok(()));
+}
+
+#[test]
+fn given_clause_enclosing_one_listed_after_a_sibling_when_adding_then_it_comes_after_both() {
+    let mut method = parse(&TINY_METHOD);
+    method.insert_prelude(vec![nop(); 10]).unwrap();
+    let type_ref = Token::type_ref(0x0100_0001).unwrap();
+    let clause = |kind, try_range, handler_range| ExceptionClause {
+        kind,
+        try_range,
+        handler_range,
+    };
+    let sibling = clause(ClauseKind::Catch(type_ref), 0..2, 2..4);
+    let enclosed = clause(ClauseKind::Finally, 5..6, 6..7);
+    let outer = clause(ClauseKind::Fault, 0..9, 9..10);
+    for existing in [&sibling, &enclosed, &outer].iter() {
+        method.add_exception_clause((*existing).clone()).unwrap();
+    }
+
+    // Protects the same instructions as `sibling`, its handler holds `enclosed`
+    let handler = clause(ClauseKind::Catch(type_ref), 0..2, 4..8);
+    method.add_exception_clause(handler.clone()).unwrap();
+    // Encloses `enclosed` and is enclosed by `handler`
+    let nested = clause(ClauseKind::Fault, 5..7, 7..8);
+    method.add_exception_clause(nested.clone()).unwrap();
+
+    assert_eq!(
+        method.exception_clauses(),
+        Ok(vec![sibling, enclosed, nested, handler, outer])
+    );
+}
@@ -0,0 +1,236 @@
+//! Method bodies and metadata shared by the CIL tests.
+#![allow(dead_code)]
+
+use clr_profiler::cil::{FatSectionClause, Method, Section};
+use clr_profiler::ffi::{
+    mdFieldDef, mdGenericParam, mdMemberRef, mdMethodDef, mdMethodSpec, mdSignature, mdString,
+    mdToken, mdTypeDef, mdTypeRef, mdTypeSpec, CorMethodAttr, CorMethodImpl, E_FAIL, HRESULT,
+};
+use clr_profiler::{
+    FieldProps, GenericParamProps, MemberRefProps, MetadataEmitTrait, MetadataImportTrait,
+    MethodProps, MethodSpecProps, TypeDefProps, TypeRefProps,
+};
+use std::cell::RefCell;
+
+/// Tiny method body:
+/// ```text
+/// IL_0000: nop
+/// IL_0001: ret
+/// ```
+pub const TINY_METHOD: [u8; 3] = [0x0A, 0x00, 0x2A];
+
+/// Fat method body:
+/// ```text
+/// IL_0000: ldarg.0
+/// IL_0001: brfalse.s IL_0005
+/// IL_0003: ldc.i4.1
+/// IL_0004: ret
+/// IL_0005: ldc.i4.0
+/// IL_0006: ret
+/// ```
+pub const BRANCHING_METHOD: [u8; 19] = [
+    0x03, 0x30, 0x01, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // header
+    0x02, 0x2C, 0x02, 0x17, 0x2A, 0x16, 0x2A,
+];
+
+/// Fat method body with a try/catch:
+/// ```text
+/// .try {
+///   IL_0000: nop
+///   IL_0001: leave.s IL_0007
+/// } catch token(0x01000001) {
+///   IL_0003: pop
+///   IL_0004: leave.s IL_0007
+/// }
+/// IL_0006: nop
+/// IL_0007: ret
+/// ```
+pub const TRY_CATCH_METHOD: [u8; 36] = [
+    0x0B, 0x30, 0x01, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // header
+    0x00, 0xDE, 0x04, 0x26, 0xDE, 0x01, 0x00, 0x2A, // code
+    0x01, 0x10, 0x00, 0x00, // small EH section header
+    0x00, 0x00, 0x00, 0x00, 0x03, 0x03, 0x00, 0x03, 0x01, 0x00, 0x00, 0x01, // catch clause
+];
+
+/// Fat method body whose code needs padding before its two sections, a small
+/// and a fat one, which both catch exceptions thrown by the same `nop`:
+/// ```text
+/// IL_0000: nop
+/// IL_0001: leave.s IL_0006
+/// IL_0003: pop
+/// IL_0004: leave.s IL_0006
+/// IL_0006: ret
+/// ```
+pub const TWO_SECTIONS_METHOD: [u8; 64] = [
+    0x0B, 0x30, 0x01, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // header
+    0x00, 0xDE, 0x03, 0x26, 0xDE, 0x00, 0x2A, // code
+    0x00, // padding
+    0x81, 0x10, 0x00, 0x00, // small EH section header, more sections follow
+    0x00, 0x00, 0x00, 0x00, 0x03, 0x03, 0x00, 0x03, 0x01, 0x00, 0x00, 0x01, // catch clause
+    0x41, 0x1C, 0x00, 0x00, // fat EH section header
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, // catch clause
+    0x03, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x01,
+];
+
+pub fn parse(bytes: &[u8]) -> Method {
+    Method::new(bytes.as_ptr(), bytes.len() as u32).unwrap()
+}
+
+/// Metadata of
+/// ```text
+/// namespace Namespace {
+///   class Outer<TKey> {
+///     TKey Add(List<int> items, int[,] grid);
+///     class Inner { static void Method<T>(int value, string text); }
+///   }
+/// }
+/// ```
+/// whose only locals signature, `0x11000001`, is `locals`. Records the
+/// signatures it is asked to emit.
+#[derive(Default)]
+pub struct FakeMetadata {
+    pub locals: Vec<u8>,
+    pub emitted: RefCell<Vec<Vec<u8>>>,
+}
+impl FakeMetadata {
+    pub fn with_locals(locals: Vec<u8>) -> Self {
+        FakeMetadata {
+            locals,
+            emitted: RefCell::new(Vec::new()),
+        }
+    }
+}
+/// Generic method of one parameter: void (int32, string)
+static GENERIC_METHOD_SIG: [u8; 6] = [0x10, 0x01, 0x02, 0x01, 0x08, 0x0E];
+/// Instance method: !0 (class List`1<int32>, int32[,])
+static INSTANCE_METHOD_SIG: [u8; 14] = [
+    0x20, 0x02, 0x13, 0x00, 0x15, 0x12, 0x05, 0x01, 0x08, 0x14, 0x08, 0x02, 0x00, 0x00,
+];
+impl MetadataImportTrait for FakeMetadata {
+    fn get_method_props(&self, mb: mdMethodDef) -> Result<MethodProps, HRESULT> {
+        let (class_token, name, sig): (_, _, &[u8]) = match mb {
+            0x0600_0001 => (0x0200_0003, "Method", &GENERIC_METHOD_SIG),
+            0x0600_0002 => (0x0200_0002, "Add", &INSTANCE_METHOD_SIG),
+            _ => return Err(E_FAIL),
+        };
+        Ok(MethodProps {
+            class_token,
+            name: name.to_string(),
+            attr_flags: CorMethodAttr::empty(),
+            sig: sig.as_ptr(),
+            sig_length: sig.len() as u32,
+            rva: 0,
+            impl_flags: CorMethodImpl::empty(),
+        })
+    }
+    fn get_typedef_props(&self, td: mdTypeDef) -> Result<TypeDefProps, HRESULT> {
+        let name = match td {
+            0x0200_0002 => "Namespace.Outer`1",
+            0x0200_0003 => "Inner",
+            _ => return Err(E_FAIL),
+        };
+        Ok(TypeDefProps {
+            name: name.to_string(),
+            attr_flags: 0,
+            base_type: 0,
+        })
+    }
+    fn get_typeref_props(&self, tr: mdTypeRef) -> Result<TypeRefProps, HRESULT> {
+        match tr {
+            0x0100_0001 => Ok(TypeRefProps {
+                name: "System.Collections.Generic.List`1".to_string(),
+                resolution_scope: 0x2300_0001,
+            }),
+            _ => Err(E_FAIL),
+        }
+    }
+    fn get_member_ref_props(&self, _: mdMemberRef) -> Result<MemberRefProps, HRESULT> {
+        Err(E_FAIL)
+    }
+    fn get_field_props(&self, _: mdFieldDef) -> Result<FieldProps, HRESULT> {
+        Err(E_FAIL)
+    }
+    fn get_user_string(&self, _: mdString) -> Result<String, HRESULT> {
+        Err(E_FAIL)
+    }
+    fn get_sig_from_token(&self, md_sig: mdSignature) -> Result<Vec<u8>, HRESULT> {
+        match md_sig {
+            0x1100_0001 => Ok(self.locals.clone()),
+            _ => Err(E_FAIL),
+        }
+    }
+    fn get_nested_class_props(&self, td: mdTypeDef) -> Result<mdTypeDef, HRESULT> {
+        match td {
+            0x0200_0003 => Ok(0x0200_0002),
+            _ => Err(E_FAIL),
+        }
+    }
+    fn enum_generic_params(&self, tk: mdToken) -> Result<Vec<mdGenericParam>, HRESULT> {
+        match tk {
+            0x0200_0002 => Ok(vec![0x2A00_0001]),
+            0x0600_0001 => Ok(vec![0x2A00_0002]),
+            _ => Ok(vec![]),
+        }
+    }
+    fn get_generic_param_props(&self, gp: mdGenericParam) -> Result<GenericParamProps, HRESULT> {
+        let (owner, name) = match gp {
+            0x2A00_0001 => (0x0200_0002, "TKey"),
+            0x2A00_0002 => (0x0600_0001, "T"),
+            _ => return Err(E_FAIL),
+        };
+        Ok(GenericParamProps {
+            seq: 0,
+            flags: 0,
+            owner,
+            name: name.to_string(),
+        })
+    }
+    fn get_type_spec_from_token(&self, typespec: mdTypeSpec) -> Result<Vec<u8>, HRESULT> {
+        match typespec {
+            // class List`1<!0>
+            0x1B00_0001 => Ok(vec![0x15, 0x12, 0x05, 0x01, 0x13, 0x00]),
+            _ => Err(E_FAIL),
+        }
+    }
+    fn get_method_spec_props(&self, mi: mdMethodSpec) -> Result<MethodSpecProps, HRESULT> {
+        match mi {
+            // Method<string[]>
+            0x2B00_0001 => Ok(MethodSpecProps {
+                parent: 0x0600_0001,
+                sig: vec![0x0A, 0x01, 0x1D, 0x0E],
+            }),
+            _ => Err(E_FAIL),
+        }
+    }
+}
+impl MetadataEmitTrait for FakeMetadata {
+    fn get_token_from_sig(&self, sig: &[u8]) -> Result<mdSignature, HRESULT> {
+        let mut emitted = self.emitted.borrow_mut();
+        emitted.push(sig.to_vec());
+        Ok(0x1100_0001 + emitted.len() as u32)
+    }
+}
+
+/// Kind flags and class token of every clause, which serialization keeps as is.
+pub fn clause_flags(method: &Method) -> Vec<(bool, bool, bool, bool, u32)> {
+    let flags = |clause: &FatSectionClause| {
+        (
+            clause.is_exception,
+            clause.is_filter,
+            clause.is_finally,
+            clause.is_fault,
+            clause.class_token_or_filter_offset,
+        )
+    };
+    method
+        .sections
+        .iter()
+        .flat_map(|section| match section {
+            Section::FatSection(_, clauses) => clauses.iter().map(flags).collect::<Vec<_>>(),
+            Section::SmallSection(_, clauses) => clauses
+                .iter()
+                .map(|clause| flags(&FatSectionClause::from(clause)))
+                .collect(),
+        })
+        .collect()
+}
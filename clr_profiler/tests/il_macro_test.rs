@@ -0,0 +1,85 @@
+mod fixtures;
+
+use clr_profiler::cil::{
+    call, ldarg_0, ldc_i4, ldc_i4_s, nop, ret, Instruction, Label, Opcode, OpcodeKind, Operand,
+    OperandParams, Token, BR, BRFALSE_S,
+};
+use clr_profiler::{il, il_opcodes};
+
+#[test]
+fn given_statement_with_several_labels_when_expanded_then_they_name_the_same_instruction() {
+    let instructions = il! {
+        first:
+        second:
+            nop;
+        third:
+            br second;
+            br first;
+            br third;
+    };
+
+    assert_eq!(
+        instructions,
+        vec![
+            nop().with_label(Label(0)),
+            Instruction::new(BR, Operand::BrTarget(Label(0))).with_label(Label(1)),
+            Instruction::new(BR, Operand::BrTarget(Label(0))),
+            Instruction::new(BR, Operand::BrTarget(Label(1))),
+        ]
+    );
+}
+
+#[test]
+fn given_il_macro_when_expanded_then_it_matches_the_constructors() {
+    let token = Token::member_ref(0x0A00_0012).unwrap();
+
+    let instructions = il! {
+            ldarg.0;
+            brfalse.s skip;
+            ldc.i4.s -2;
+            ldc.i4 0xFFFFFFFF;
+            call { token };
+            call 0x06000003;
+        skip:
+            ret;
+    };
+
+    assert_eq!(
+        instructions,
+        vec![
+            ldarg_0(),
+            Instruction::new(BRFALSE_S, Operand::BrTarget(Label(0))),
+            ldc_i4_s(0xFE),
+            ldc_i4(-1),
+            call(token).unwrap(),
+            call(Token::method_def(0x0600_0003).unwrap()).unwrap(),
+            ret().with_label(Label(0)),
+        ]
+    );
+}
+
+#[test]
+fn given_every_opcode_when_looking_up_its_mnemonic_then_the_macro_maps_it_to_the_same_opcode() {
+    let table: &[(&str, Opcode, OperandParams)] = il_opcodes!();
+    let one_byte = (0x00..=0xFF).map(Opcode::from_byte);
+    let two_bytes = (0x00..=0xFF).filter_map(|byte| Opcode::from_byte_pair((0xFE, byte)).ok());
+    let opcodes: Vec<_> = one_byte
+        .chain(two_bytes)
+        .filter(|opcode| opcode.name != "unused" && opcode.opcode_kind != OpcodeKind::Internal)
+        .collect();
+
+    for opcode in opcodes.iter() {
+        let entry = table.iter().find(|(name, _, _)| *name == opcode.name);
+        assert_eq!(
+            entry.map(|(_, opcode, _)| (opcode.byte_1, opcode.byte_2)),
+            Some((opcode.byte_1, opcode.byte_2)),
+            "{}",
+            opcode.name
+        );
+    }
+    for (name, opcode, operand_params) in table.iter() {
+        assert_eq!(*name, opcode.name);
+        assert_eq!(*operand_params, opcode.operand_params, "{}", name);
+    }
+    assert_eq!(table.len(), opcodes.len());
+}
@@ -0,0 +1,58 @@
+mod fixtures;
+
+use clr_profiler::cil::{append_locals, Error, MethodHeader, Type};
+use clr_profiler::ffi::CorElementType;
+use fixtures::{parse, FakeMetadata, BRANCHING_METHOD};
+
+#[test]
+fn given_local_signatures_when_appending_locals_then_they_are_decoded_and_reencoded() {
+    let int32 = Type::Primitive(CorElementType::ELEMENT_TYPE_I4);
+    let string = Type::Primitive(CorElementType::ELEMENT_TYPE_STRING);
+    // int32, pinned int32&
+    let pinned = vec![0x07, 0x02, 0x08, 0x45, 0x10, 0x08];
+
+    assert_eq!(append_locals(&[], &[int32]), Ok(vec![0x07, 0x01, 0x08]));
+    assert_eq!(
+        append_locals(&pinned, &[string]),
+        Ok(vec![0x07, 0x03, 0x08, 0x45, 0x10, 0x08, 0x0E])
+    );
+    // A field signature, and a local of an element type which isn't a type
+    assert_eq!(
+        append_locals(&[0x06, 0x08], &[]),
+        Err(Error::InvalidSignature)
+    );
+    let sentinel = Type::Primitive(CorElementType::ELEMENT_TYPE_SENTINEL);
+    assert_eq!(
+        append_locals(&pinned, &[sentinel]),
+        Err(Error::InvalidSignature)
+    );
+}
+
+#[test]
+fn given_existing_locals_when_adding_locals_then_they_are_appended_to_a_new_signature() {
+    let metadata = FakeMetadata::with_locals(vec![0x07, 0x01, 0x08]); // int32
+    let mut method = parse(&BRANCHING_METHOD);
+    method.set_local_var_sig_tok(0x1100_0001);
+    let instructions = method.instructions.clone();
+
+    let locals = [
+        Type::Primitive(CorElementType::ELEMENT_TYPE_I8),
+        Type::Primitive(CorElementType::ELEMENT_TYPE_STRING),
+    ];
+
+    let first = method.add_locals(&metadata, &metadata, &locals).unwrap();
+
+    assert_eq!(first, 1);
+    assert_eq!(
+        metadata.emitted.into_inner(),
+        vec![vec![0x07, 0x03, 0x08, 0x0A, 0x0E]]
+    );
+    match method.method_header {
+        MethodHeader::Fat(header) => {
+            assert_eq!(header.local_var_sig_tok, 0x1100_0002);
+            assert!(header.init_locals);
+        }
+        MethodHeader::Tiny(_) => panic!("header should be fat"),
+    }
+    assert_eq!(method.instructions, instructions);
+}
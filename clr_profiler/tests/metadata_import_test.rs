@@ -0,0 +1,163 @@
+use clr_profiler::ffi::{mdToken, E_FAIL, HCORENUM, HRESULT, S_FALSE, S_OK, ULONG};
+use clr_profiler::MetadataEnum;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// Fake `Enum*` call handing out `tokens` a page at a time, then `S_FALSE`.
+/// Records the size of each page it's asked to fill.
+fn fake_fetch(
+    tokens: Vec<mdToken>,
+    pages: Rc<RefCell<Vec<usize>>>,
+) -> impl FnMut(&mut HCORENUM, &mut [mdToken], &mut ULONG) -> HRESULT {
+    let mut remaining = tokens.into_iter();
+    move |enum_handle, page, count| {
+        *enum_handle = 0x1 as HCORENUM;
+        pages.borrow_mut().push(page.len());
+        let mut fetched = 0;
+        for (slot, token) in page.iter_mut().zip(&mut remaining) {
+            *slot = token;
+            fetched += 1;
+        }
+        *count = fetched;
+        if fetched == 0 {
+            S_FALSE
+        } else {
+            S_OK
+        }
+    }
+}
+
+fn count_closes(closes: &Rc<Cell<usize>>) -> impl FnMut(HCORENUM) {
+    let closes = closes.clone();
+    move |enum_handle| {
+        assert_eq!(enum_handle, 0x1 as HCORENUM);
+        closes.set(closes.get() + 1);
+    }
+}
+
+#[test]
+fn given_exactly_one_page_of_tokens_when_enumerating_then_all_are_yielded() {
+    let tokens: Vec<mdToken> = (1..=MetadataEnum::PAGE_SIZE as u32)
+        .map(|rid| 0x0200_0000 | rid)
+        .collect();
+    let pages = Rc::new(RefCell::new(Vec::new()));
+    let closes = Rc::new(Cell::new(0));
+
+    let enumerated = MetadataEnum::new(
+        fake_fetch(tokens.clone(), pages.clone()),
+        count_closes(&closes),
+    )
+    .collect::<Result<Vec<_>, _>>();
+
+    assert_eq!(enumerated, Ok(tokens));
+    assert_eq!(
+        *pages.borrow(),
+        vec![MetadataEnum::PAGE_SIZE, MetadataEnum::PAGE_SIZE]
+    );
+    assert_eq!(closes.get(), 1);
+}
+
+#[test]
+fn given_tokens_past_a_page_when_enumerating_then_the_next_page_is_fetched() {
+    let tokens: Vec<mdToken> = (1..=MetadataEnum::PAGE_SIZE as u32 + 1)
+        .map(|rid| 0x0200_0000 | rid)
+        .collect();
+    let pages = Rc::new(RefCell::new(Vec::new()));
+    let closes = Rc::new(Cell::new(0));
+
+    let enumerated = MetadataEnum::new(
+        fake_fetch(tokens.clone(), pages.clone()),
+        count_closes(&closes),
+    )
+    .collect::<Result<Vec<_>, _>>();
+
+    assert_eq!(enumerated, Ok(tokens));
+    assert_eq!(pages.borrow().len(), 3);
+    assert_eq!(closes.get(), 1);
+}
+
+#[test]
+fn given_s_ok_with_no_tokens_when_enumerating_then_it_ends() {
+    let fetches = Rc::new(Cell::new(0));
+    let closes = Rc::new(Cell::new(0));
+    let fetched = fetches.clone();
+
+    let mut enumeration = MetadataEnum::new(
+        move |enum_handle, _page, count| {
+            *enum_handle = 0x1 as HCORENUM;
+            fetched.set(fetched.get() + 1);
+            *count = 0;
+            S_OK
+        },
+        count_closes(&closes),
+    );
+
+    assert_eq!(enumeration.next(), None);
+    assert_eq!(enumeration.next(), None);
+    assert_eq!(fetches.get(), 1);
+    drop(enumeration);
+    assert_eq!(closes.get(), 1);
+}
+
+#[test]
+fn given_failing_fetch_after_a_page_when_enumerating_then_the_error_ends_it() {
+    let fetches = Rc::new(Cell::new(0));
+    let closes = Rc::new(Cell::new(0));
+    let fetched = fetches.clone();
+
+    let mut enumeration = MetadataEnum::new(
+        move |enum_handle, page, count| {
+            *enum_handle = 0x1 as HCORENUM;
+            fetched.set(fetched.get() + 1);
+            if fetched.get() > 1 {
+                *count = 0;
+                return E_FAIL;
+            }
+            page[..2].copy_from_slice(&[0x0600_0001, 0x0600_0002]);
+            *count = 2;
+            S_OK
+        },
+        count_closes(&closes),
+    );
+
+    assert_eq!(enumeration.next(), Some(Ok(0x0600_0001)));
+    assert_eq!(enumeration.next(), Some(Ok(0x0600_0002)));
+    assert_eq!(enumeration.next(), Some(Err(E_FAIL)));
+    assert_eq!(enumeration.next(), None);
+    assert_eq!(fetches.get(), 2);
+    drop(enumeration);
+    assert_eq!(closes.get(), 1);
+}
+
+#[test]
+fn given_enumeration_stopped_early_when_dropped_then_it_is_closed_once() {
+    let tokens: Vec<mdToken> = (1..=100).map(|rid| 0x0200_0000 | rid).collect();
+    let pages = Rc::new(RefCell::new(Vec::new()));
+    let closes = Rc::new(Cell::new(0));
+
+    let mut enumeration = MetadataEnum::new(fake_fetch(tokens, pages), count_closes(&closes));
+    assert_eq!(enumeration.next(), Some(Ok(0x0200_0001)));
+    assert_eq!(closes.get(), 0);
+    drop(enumeration);
+
+    assert_eq!(closes.get(), 1);
+}
+
+#[test]
+fn given_no_enum_handle_when_dropped_then_it_is_not_closed() {
+    let closes = Rc::new(Cell::new(0));
+
+    let unfetched = MetadataEnum::new(|_, _, _| S_FALSE, count_closes(&closes));
+    drop(unfetched);
+    let mut empty = MetadataEnum::new(
+        |_, _, count| {
+            *count = 0;
+            S_FALSE
+        },
+        count_closes(&closes),
+    );
+    assert_eq!(empty.next(), None);
+    drop(empty);
+
+    assert_eq!(closes.get(), 0);
+}
@@ -0,0 +1,340 @@
+mod fixtures;
+
+use clr_profiler::cil::{
+    call, ldc_i4_1, nop, ret, Error, FatMethodHeader, Instruction, Label, Method, MethodHeader,
+    Operand, Section, Token, Type, WrapHandler, BR_S,
+};
+use clr_profiler::ffi::CorElementType;
+use clr_profiler::il;
+use fixtures::{
+    clause_flags, parse, FakeMetadata, BRANCHING_METHOD, TINY_METHOD, TRY_CATCH_METHOD,
+    TWO_SECTIONS_METHOD,
+};
+use quickcheck::{quickcheck, QuickCheck, TestResult};
+
+#[test]
+fn given_branch_over_insertion_point_when_inserting_then_branch_offset_grows() {
+    let mut method = parse(&BRANCHING_METHOD);
+    method.insert(2, vec![nop(), nop()]).unwrap();
+
+    let bytes = method.into_bytes().unwrap();
+
+    assert_eq!(bytes[4], 0x09); // code size
+    assert_eq!(
+        &bytes[12..],
+        &[0x02, 0x2C, 0x04, 0x00, 0x00, 0x17, 0x2A, 0x16, 0x2A]
+    );
+}
+
+#[test]
+fn given_branch_target_when_inserting_before_it_then_branch_skips_inserted_code() {
+    let mut method = parse(&BRANCHING_METHOD);
+    method
+        .insert(4, vec![call(Token::member_ref(0x0A00_0001).unwrap()).unwrap()])
+        .unwrap();
+
+    let bytes = method.into_bytes().unwrap();
+
+    assert_eq!(
+        &bytes[12..],
+        &[0x02, 0x2C, 0x07, 0x17, 0x2A, 0x28, 0x01, 0x00, 0x00, 0x0A, 0x16, 0x2A]
+    );
+}
+
+#[test]
+fn given_local_labels_when_inserting_then_labels_do_not_collide() {
+    let mut method = parse(&BRANCHING_METHOD);
+    let skip = Label(0x05); // Same name as the method's own branch target
+    let prelude = vec![
+        Instruction::new(BR_S, Operand::BrTarget(skip)),
+        ldc_i4_1(),
+        nop().with_label(skip),
+    ];
+    method.insert_prelude(prelude).unwrap();
+
+    let bytes = method.into_bytes().unwrap();
+
+    assert_eq!(
+        &bytes[12..],
+        &[0x2B, 0x01, 0x17, 0x00, 0x02, 0x2C, 0x02, 0x17, 0x2A, 0x16, 0x2A]
+    );
+}
+
+#[test]
+fn given_local_label_and_raw_branch_to_the_end_when_inserting_then_labels_do_not_collide() {
+    let mut method = parse(&TINY_METHOD);
+    let start = Label(0x07);
+    let prelude = vec![
+        nop().with_label(start),
+        Instruction::new(BR_S, Operand::ShortInlineBrTarget(2)),
+        Instruction::new(BR_S, Operand::BrTarget(start)),
+    ];
+    method.insert_prelude(prelude).unwrap();
+
+    let bytes = method.into_bytes().unwrap();
+
+    assert_eq!(&bytes[1..], &[0x00, 0x2B, 0x02, 0x2B, 0xFB, 0x00, 0x2A]);
+}
+
+#[test]
+fn given_index_past_the_end_when_inserting_or_removing_then_out_of_range_is_returned() {
+    let mut method = parse(&TINY_METHOD);
+
+    assert_eq!(method.insert(3, vec![nop()]), Err(Error::OutOfRange));
+    assert_eq!(method.remove(1..3), Err(Error::OutOfRange));
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = 2..1;
+    assert_eq!(method.remove(reversed), Err(Error::OutOfRange));
+    assert_eq!(method.instructions.len(), 2);
+}
+
+#[test]
+fn given_raw_offsets_in_prelude_when_inserting_then_they_are_kept_relative_to_the_prelude() {
+    let mut method = parse(&BRANCHING_METHOD);
+    let prelude = vec![
+        Instruction::new(BR_S, Operand::ShortInlineBrTarget(1)),
+        ldc_i4_1(),
+    ];
+    method.insert_prelude(prelude).unwrap();
+    method.insert(2, vec![nop()]).unwrap();
+
+    let bytes = method.into_bytes().unwrap();
+
+    assert_eq!(&bytes[12..16], &[0x2B, 0x02, 0x17, 0x00]);
+}
+
+#[test]
+fn given_removed_branch_target_when_serializing_then_branch_moves_to_next_instruction() {
+    let mut method = parse(&BRANCHING_METHOD);
+    method.remove(4..5).unwrap();
+
+    let bytes = method.into_bytes().unwrap();
+
+    assert_eq!(&bytes[12..], &[0x02, 0x2C, 0x02, 0x17, 0x2A, 0x2A]);
+}
+
+#[test]
+fn given_try_block_when_inserting_inside_then_clause_grows_with_it() {
+    let mut method = parse(&TRY_CATCH_METHOD);
+    method.insert(1, vec![nop(), nop()]).unwrap();
+    method.insert_prelude(vec![nop()]).unwrap();
+
+    let labels = match &method.sections[0] {
+        Section::SmallSection(_, clauses) => clauses[0].labels.unwrap(),
+        _ => panic!("Expected a small section"),
+    };
+    assert_eq!(method.position(labels.try_start), Some(1));
+    assert_eq!(method.position(labels.try_last), Some(4));
+    assert_eq!(method.position(labels.handler_start), Some(5));
+    assert_eq!(method.position(labels.handler_last), Some(6));
+}
+
+#[test]
+fn given_unknown_label_when_serializing_then_error_is_returned() {
+    let mut method = parse(&BRANCHING_METHOD);
+    method
+        .insert_prelude(vec![Instruction::new(BR_S, Operand::BrTarget(Label(0x99)))])
+        .unwrap();
+    method.instructions.push(ret());
+
+    match method.into_bytes() {
+        Err(Error::UndefinedLabel(Label(0x99))) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn given_tiny_method_when_code_outgrows_it_then_header_is_promoted_to_fat() {
+    let mut method = parse(&TINY_METHOD);
+    method.insert_prelude(vec![nop(); 62]).unwrap();
+
+    let bytes = method.into_bytes().unwrap();
+
+    assert_eq!(bytes.len(), 12 + 64);
+    assert_eq!(
+        Method::new(bytes.as_ptr(), bytes.len() as u32)
+            .unwrap()
+            .method_header,
+        MethodHeader::Fat(FatMethodHeader {
+            more_sects: false,
+            init_locals: false,
+            max_stack: 8,
+            code_size: 64,
+            local_var_sig_tok: 0,
+        })
+    );
+}
+
+#[test]
+fn given_tiny_method_when_code_still_fits_then_header_stays_tiny() {
+    let mut method = parse(&TINY_METHOD);
+    method.insert_prelude(vec![nop(); 61]).unwrap();
+
+    let bytes = method.into_bytes().unwrap();
+
+    assert_eq!(bytes[0], (63 << 2) | 0x02);
+    assert_eq!(bytes.len(), 1 + 63);
+}
+
+#[test]
+fn given_tiny_method_when_raising_max_stack_then_header_is_promoted_to_fat() {
+    let mut method = parse(&TINY_METHOD);
+    method.set_max_stack(8);
+    assert!(matches!(method.method_header, MethodHeader::Tiny(_)));
+
+    method.set_max_stack(9);
+
+    let bytes = method.into_bytes().unwrap();
+    assert_eq!(
+        &bytes[..12],
+        &[0x03, 0x30, 0x09, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(&bytes[12..], &[0x00, 0x2A]);
+}
+
+#[test]
+fn given_small_section_when_try_block_outgrows_it_then_section_is_promoted_to_fat() {
+    let mut method = parse(&TRY_CATCH_METHOD);
+    method.insert(1, vec![nop(); 256]).unwrap();
+
+    let bytes = method.into_bytes().unwrap();
+
+    let section = &bytes[bytes.len() - 28..];
+    assert_eq!(&section[..4], &[0x41, 0x1C, 0x00, 0x00]); // fat EH table, 28 bytes
+    assert_eq!(
+        &section[4..],
+        &[
+            0x00, 0x00, 0x00, 0x00, // catch
+            0x00, 0x00, 0x00, 0x00, 0x03, 0x01, 0x00, 0x00, // try
+            0x03, 0x01, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, // handler
+            0x01, 0x00, 0x00, 0x01, // class token
+        ]
+    );
+}
+
+#[test]
+fn given_prelude_when_building_il_map_then_original_offsets_map_past_it() {
+    let mut method = parse(&BRANCHING_METHOD);
+    method.insert_prelude(vec![nop(), nop()]).unwrap();
+
+    let map: Vec<(u32, u32)> = method
+        .il_map()
+        .unwrap()
+        .iter()
+        .map(|entry| (entry.oldOffset, entry.newOffset))
+        .collect();
+
+    assert_eq!(map, vec![(0, 2), (1, 3), (3, 5), (4, 6), (5, 7), (6, 8)]);
+}
+
+#[test]
+fn given_truncated_bodies_when_parsing_then_offset_and_context_are_reported() {
+    for length in 0..TRY_CATCH_METHOD.len() {
+        assert!(Method::parse(&TRY_CATCH_METHOD[..length]).is_err());
+    }
+
+    assert_eq!(
+        Method::parse(&[]),
+        Err(Error::Malformed(0, "truncated method header".to_string()))
+    );
+    assert_eq!(
+        Method::parse(&BRANCHING_METHOD[..15]),
+        Err(Error::Malformed(
+            12,
+            "code size 7 runs past the end of the body".to_string()
+        ))
+    );
+    assert_eq!(
+        Method::parse(&[0x0A, 0x00, 0x1F]),
+        Err(Error::Malformed(
+            2,
+            "truncated operand of `ldc.i4.s`".to_string()
+        ))
+    );
+    assert_eq!(
+        Method::parse(&TRY_CATCH_METHOD[..28]),
+        Err(Error::Malformed(
+            20,
+            "section 0 runs past the end of the body".to_string()
+        ))
+    );
+}
+
+#[test]
+fn given_arbitrary_bytes_when_parsing_then_it_never_panics() {
+    fn any_body(body: Vec<u8>) -> bool {
+        let _ = Method::parse(&body);
+        true
+    }
+    fn fat_body(bytes: Vec<u8>, more_sects: bool) -> bool {
+        // The first half is code, the rest is parsed as sections when flagged
+        let mut body = vec![if more_sects { 0x0B } else { 0x03 }, 0x30, 0x08, 0x00];
+        body.extend_from_slice(&(bytes.len() as u32 / 2).to_le_bytes());
+        body.extend_from_slice(&[0x00; 4]);
+        body.extend(bytes);
+        any_body(body)
+    }
+    fn mutated_try_catch(index: usize, value: u8) -> bool {
+        let mut body = TRY_CATCH_METHOD.to_vec();
+        body[index % TRY_CATCH_METHOD.len()] = value;
+        any_body(body)
+    }
+    quickcheck(any_body as fn(Vec<u8>) -> bool);
+    quickcheck(fat_body as fn(Vec<u8>, bool) -> bool);
+    quickcheck(mutated_try_catch as fn(usize, u8) -> bool);
+}
+
+#[test]
+fn given_parsed_code_when_serializing_then_same_bytes_are_produced() {
+    fn round_trip(code: Vec<u8>) -> TestResult {
+        let mut body = vec![0x03, 0x30, 0x08, 0x00];
+        body.extend_from_slice(&(code.len() as u32).to_le_bytes());
+        body.extend_from_slice(&[0x00; 4]);
+        body.extend(code);
+        match Method::parse(&body) {
+            Ok(method) => TestResult::from_bool(method.into_bytes() == Ok(body)),
+            Err(_) => TestResult::discard(),
+        }
+    }
+    QuickCheck::new()
+        .tests(1000)
+        .max_tests(100_000)
+        .quickcheck(round_trip as fn(Vec<u8>) -> TestResult);
+}
+
+#[test]
+fn given_unmodified_bodies_when_serializing_then_bytes_are_identical() {
+    let bodies: [&[u8]; 4] = [
+        &TINY_METHOD,
+        &BRANCHING_METHOD,
+        &TRY_CATCH_METHOD,
+        &TWO_SECTIONS_METHOD,
+    ];
+
+    for body in bodies.iter() {
+        assert_eq!(parse(body).into_bytes(), Ok(body.to_vec()));
+    }
+}
+
+#[test]
+fn given_rewritten_method_when_validating_roundtrip_then_serialized_bytes_are_returned() {
+    let metadata = FakeMetadata::default();
+    let mut method = parse(&TWO_SECTIONS_METHOD);
+    let void = Type::Primitive(CorElementType::ELEMENT_TYPE_VOID);
+    let return_local = method
+        .wrap(
+            WrapHandler::Fault,
+            il! { call 0x0A00_0001 },
+            &void,
+            &metadata,
+            &metadata,
+        )
+        .unwrap();
+
+    let bytes = method.validate_roundtrip().unwrap();
+
+    assert_eq!(return_local, None);
+    assert!(metadata.emitted.into_inner().is_empty());
+    assert_eq!(clause_flags(&parse(&bytes)), clause_flags(&method));
+    assert_eq!(Ok(bytes), method.into_bytes());
+}
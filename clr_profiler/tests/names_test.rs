@@ -0,0 +1,91 @@
+mod fixtures;
+
+use clr_profiler::cil::{csharp_name, ilasm_name, method_name, Error, NameStyle};
+use clr_profiler::ffi::E_FAIL;
+use fixtures::FakeMetadata;
+
+#[test]
+fn given_nested_generic_method_when_naming_then_type_and_signature_are_included() {
+    let metadata = FakeMetadata::default();
+    let string = vec!["string".to_string()];
+    assert_eq!(
+        method_name(&metadata, 0x0600_0001, &[], &[]),
+        Ok("Namespace.Outer`1+Inner.Method<T>(int32, string) : void".to_string())
+    );
+    assert_eq!(
+        method_name(&metadata, 0x0600_0001, &[], &string),
+        Ok("Namespace.Outer`1+Inner.Method<string>(int32, string) : void".to_string())
+    );
+    assert_eq!(
+        method_name(&metadata, 0x0600_0002, &[], &[]),
+        Ok(
+            "Namespace.Outer<TKey>.Add(System.Collections.Generic.List<int32>, int32[,]) : TKey"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        method_name(&metadata, 0x0600_0002, &string, &[]),
+        Ok(
+            "Namespace.Outer<string>.Add(System.Collections.Generic.List<int32>, int32[,]) : string"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        method_name(&metadata, 0x0600_0003, &[], &[]),
+        Err(Error::Metadata(E_FAIL))
+    );
+}
+
+#[test]
+fn given_method_of_generic_type_without_type_args_when_naming_then_arity_is_replaced_by_params() {
+    let metadata = FakeMetadata::default();
+
+    let name = method_name(&metadata, 0x0600_0002, &[], &[]).unwrap();
+
+    assert!(name.starts_with("Namespace.Outer<TKey>.Add("));
+    assert!(!name.contains('`'));
+}
+
+#[test]
+fn given_runtime_type_names_when_formatting_for_ilasm_then_keywords_and_full_names_are_used() {
+    let int32 = vec!["int32".to_string()];
+    assert_eq!(NameStyle::ILAsm.class_name("System.Int32", &[]), "int32");
+    assert_eq!(NameStyle::CSharp.class_name("System.Int32", &[]), "int");
+    assert_eq!(
+        ilasm_name("System.Collections.Generic.List`1", &int32),
+        "System.Collections.Generic.List<int32>"
+    );
+    assert_eq!(
+        ilasm_name("Namespace.Outer`1+Inner", &int32),
+        "Namespace.Outer`1+Inner<int32>"
+    );
+    assert_eq!(ilasm_name("System.Decimal", &[]), "System.Decimal");
+    assert_eq!(csharp_name("System.Decimal", &[]), "decimal");
+}
+
+#[test]
+fn given_runtime_type_names_when_formatting_for_csharp_then_arity_and_namespaces_are_dropped() {
+    let args = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(csharp_name("System.Int32", &[]), "int");
+    assert_eq!(csharp_name("System.Object", &[]), "object");
+    assert_eq!(
+        csharp_name(
+            "System.Collections.Generic.Dictionary`2",
+            &args(&["string", "List<int>"])
+        ),
+        "Dictionary<string, List<int>>"
+    );
+    assert_eq!(
+        csharp_name("Namespace.Outer`1+Inner`1", &args(&["int", "string"])),
+        "Outer<int>.Inner<string>"
+    );
+    assert_eq!(
+        csharp_name("Namespace.Outer`1+Inner", &args(&["int"])),
+        "Outer<int>.Inner"
+    );
+}
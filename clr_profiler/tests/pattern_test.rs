@@ -0,0 +1,51 @@
+mod fixtures;
+
+use clr_profiler::cil::{
+    assemble_method, Operand, Pattern, PatternMatch, Token, CALL, CALLVIRT, LDSTR, NEWOBJ,
+};
+
+#[test]
+fn given_string_passed_to_call_when_finding_pattern_then_indices_and_operands_are_reported() {
+    let method = assemble_method(
+        "\
+  ldstr token(0x70000001)
+  call token(0x0A000012)
+  ldstr token(0x70000002)
+  call token(0x0A000013)
+  ldstr token(0x70000003)
+  call token(0x0A000012)
+  newobj token(0x0A000014)
+  ret",
+    )
+    .unwrap();
+    let log = Token::member_ref(0x0A00_0012).unwrap();
+
+    let pattern = Pattern::new()
+        .opcode(LDSTR)
+        .capture()
+        .any_of(&[CALL, CALLVIRT])
+        .token(log);
+
+    assert_eq!(
+        method.find(&pattern),
+        vec![
+            PatternMatch {
+                range: 0..2,
+                captures: vec![Operand::InlineString(
+                    Token::user_string(0x7000_0001).unwrap()
+                )],
+            },
+            PatternMatch {
+                range: 4..6,
+                captures: vec![Operand::InlineString(
+                    Token::user_string(0x7000_0003).unwrap()
+                )],
+            },
+        ]
+    );
+    let any_newobj = Pattern::new()
+        .opcode(NEWOBJ)
+        .operand(|operand| operand.token().map(|token| token.rid()) == Some(0x14))
+        .any();
+    assert_eq!(method.find(&any_newobj)[0].range, 6..8);
+}
@@ -0,0 +1,29 @@
+mod fixtures;
+
+use clr_profiler::cil::{ldc_i4_1, nop, shrink_branches, Instruction, Label, Operand, BR, BR_S};
+use fixtures::{parse, BRANCHING_METHOD};
+
+#[test]
+fn given_short_branch_when_target_moves_out_of_range_then_branch_is_widened() {
+    let mut method = parse(&BRANCHING_METHOD);
+    method.insert(2, vec![nop(); 200]).unwrap();
+
+    let bytes = method.into_bytes().unwrap();
+
+    assert_eq!(&bytes[12..18], &[0x02, 0x39, 0xCA, 0x00, 0x00, 0x00]); // brfalse IL_00D0
+    assert_eq!(&bytes[bytes.len() - 4..], &[0x17, 0x2A, 0x16, 0x2A]);
+}
+
+#[test]
+fn given_long_branch_within_short_range_when_shrinking_then_short_form_is_used() {
+    let skip = Label(0);
+    let mut instructions = vec![
+        Instruction::new(BR, Operand::BrTarget(skip)),
+        ldc_i4_1(),
+        nop().with_label(skip),
+    ];
+
+    shrink_branches(&mut instructions).unwrap();
+
+    assert_eq!(instructions[0].opcode, BR_S);
+}
@@ -0,0 +1,168 @@
+mod fixtures;
+
+use clr_profiler::cil::{
+    ArrayShape, CallingConvention, Error, MethodSignature, Signature, Token, Type,
+};
+use clr_profiler::ffi::CorElementType;
+use quickcheck::{quickcheck, QuickCheck, TestResult};
+
+#[test]
+fn given_generic_instance_method_blob_when_decoding_then_types_are_typed() {
+    // instance !!0 M<T>(int32&, class List`1<!!0>, string[], valuetype Point[0...,0...])
+    let blob = [
+        0x30, 0x01, 0x04, 0x1E, 0x00, 0x10, 0x08, 0x15, 0x12, 0x05, 0x01, 0x1E, 0x00, 0x1D, 0x0E,
+        0x14, 0x11, 0x08, 0x02, 0x00, 0x02, 0x00, 0x00,
+    ];
+
+    let signature = MethodSignature::from_bytes(&blob).unwrap();
+
+    assert_eq!(
+        signature,
+        MethodSignature {
+            has_this: true,
+            explicit_this: false,
+            calling_convention: CallingConvention::Default,
+            generic_param_count: 1,
+            return_type: Type::MVar(0),
+            params: vec![
+                Type::ByRef(Box::new(Type::Primitive(CorElementType::ELEMENT_TYPE_I4))),
+                Type::GenericInst {
+                    value_type: false,
+                    generic: Token::type_ref(0x0100_0001).unwrap(),
+                    args: vec![Type::MVar(0)],
+                },
+                Type::SzArray(Box::new(Type::Primitive(
+                    CorElementType::ELEMENT_TYPE_STRING
+                ))),
+                Type::Array(
+                    Box::new(Type::ValueType(Token::type_def(0x0200_0002).unwrap())),
+                    ArrayShape {
+                        rank: 2,
+                        sizes: vec![],
+                        lower_bounds: vec![0, 0],
+                    },
+                ),
+            ],
+            sentinel: None,
+        }
+    );
+    assert_eq!(signature.arg_count(), 5);
+    for length in 0..blob.len() {
+        assert_eq!(
+            Signature::from_bytes(&blob[..length]),
+            Err(Error::InvalidSignature)
+        );
+    }
+}
+
+#[test]
+fn given_other_blobs_when_decoding_then_their_kind_is_recognized() {
+    // pinned int32&, modreq(IsVolatile) int32
+    assert_eq!(
+        Signature::from_bytes(&[0x07, 0x02, 0x45, 0x10, 0x08, 0x1F, 0x05, 0x08]),
+        Ok(Signature::LocalVar(vec![
+            Type::Pinned(Box::new(Type::ByRef(Box::new(Type::Primitive(
+                CorElementType::ELEMENT_TYPE_I4
+            ))))),
+            Type::Modified {
+                required: true,
+                modifier: Token::type_ref(0x0100_0001).unwrap(),
+                modified: Box::new(Type::Primitive(CorElementType::ELEMENT_TYPE_I4)),
+            },
+        ]))
+    );
+    assert_eq!(
+        Signature::from_bytes(&[0x06, 0x13, 0x01]),
+        Ok(Signature::Field(Type::Var(1)))
+    );
+    assert_eq!(
+        Signature::from_bytes(&[0x0A, 0x01, 0x1C]),
+        Ok(Signature::MethodSpec(vec![Type::Primitive(
+            CorElementType::ELEMENT_TYPE_OBJECT
+        )]))
+    );
+    // int32[-1...]
+    assert_eq!(
+        Type::from_bytes(&[0x14, 0x08, 0x01, 0x00, 0x01, 0x7F]),
+        Ok(Type::Array(
+            Box::new(Type::Primitive(CorElementType::ELEMENT_TYPE_I4)),
+            ArrayShape {
+                rank: 1,
+                sizes: vec![],
+                lower_bounds: vec![-1],
+            }
+        ))
+    );
+    // vararg void(int32, ..., string)
+    let vararg = MethodSignature::from_bytes(&[0x05, 0x02, 0x01, 0x08, 0x41, 0x0E]).unwrap();
+    assert_eq!(vararg.sentinel, Some(1));
+    assert_eq!(Type::from_bytes(&[0x0F; 100]), Err(Error::InvalidSignature));
+}
+
+#[test]
+fn given_helper_signature_when_encoding_then_blob_is_compressed() {
+    // static void Enter(object, valuetype Context&, !!0[0...])
+    let mut signature = MethodSignature::new(
+        false,
+        Type::Primitive(CorElementType::ELEMENT_TYPE_VOID),
+        vec![
+            Type::Primitive(CorElementType::ELEMENT_TYPE_OBJECT),
+            Type::ByRef(Box::new(Type::ValueType(
+                Token::type_ref(0x0100_0123).unwrap(),
+            ))),
+            Type::Array(
+                Box::new(Type::MVar(0)),
+                ArrayShape {
+                    rank: 1,
+                    sizes: vec![],
+                    lower_bounds: vec![-8192],
+                },
+            ),
+        ],
+    );
+    signature.generic_param_count = 1;
+
+    let blob = signature.to_bytes().unwrap();
+
+    assert_eq!(
+        blob,
+        vec![
+            0x10, 0x01, 0x03, 0x01, 0x1C, 0x10, 0x11, 0x84, 0x8D, 0x14, 0x1E, 0x00, 0x01, 0x00,
+            0x01, 0x80, 0x01,
+        ]
+    );
+    assert_eq!(MethodSignature::from_bytes(&blob), Ok(signature));
+    assert_eq!(
+        Signature::LocalVar(vec![Type::Class(Token::method_def(0x0600_0001).unwrap())]).to_bytes(),
+        Err(Error::InvalidToken(0x0600_0001))
+    );
+}
+
+#[test]
+fn given_decoded_blobs_when_encoding_then_they_decode_the_same() {
+    fn round_trip(blob: Vec<u8>) -> TestResult {
+        match Signature::from_bytes(&blob) {
+            Ok(signature) => {
+                let encoded = signature.to_bytes().unwrap();
+                TestResult::from_bool(Signature::from_bytes(&encoded) == Ok(signature))
+            }
+            Err(_) => TestResult::discard(),
+        }
+    }
+    fn lower_bound(value: i32) -> bool {
+        let value = value >> 3;
+        let array = Type::Array(
+            Box::new(Type::Primitive(CorElementType::ELEMENT_TYPE_I4)),
+            ArrayShape {
+                rank: 1,
+                sizes: vec![],
+                lower_bounds: vec![value],
+            },
+        );
+        Type::from_bytes(&array.to_bytes().unwrap()) == Ok(array)
+    }
+    QuickCheck::new()
+        .tests(1000)
+        .quickcheck(round_trip as fn(Vec<u8>) -> TestResult);
+    quickcheck(lower_bound as fn(i32) -> bool);
+}
@@ -0,0 +1,70 @@
+mod fixtures;
+
+use clr_profiler::cil::{call, ldc_i4_1, pop, Error, MethodHeader, Token};
+use fixtures::{parse, BRANCHING_METHOD, TINY_METHOD, TRY_CATCH_METHOD};
+
+#[test]
+fn given_probe_call_in_prelude_when_updating_max_stack_then_it_covers_the_arguments() {
+    let mut method = parse(&BRANCHING_METHOD);
+    let probe = vec![
+        ldc_i4_1(),
+        ldc_i4_1(),
+        ldc_i4_1(),
+        call(Token::member_ref(0x0A00_0001).unwrap()).unwrap(),
+        pop(),
+    ];
+    method.insert_prelude(probe).unwrap();
+
+    // instance int32 Probe(int32, int32)
+    let signature = |token| match token {
+        0x0A00_0001 => Some(vec![0x20, 0x02, 0x08, 0x08, 0x08]),
+        _ => None,
+    };
+    method.update_max_stack(signature).unwrap();
+
+    match method.method_header {
+        MethodHeader::Fat(header) => assert_eq!(header.max_stack, 3),
+        _ => panic!("Expected a fat header"),
+    }
+}
+
+#[test]
+fn given_vararg_call_with_modified_void_return_when_computing_max_stack_then_nothing_is_pushed() {
+    let mut method = parse(&TINY_METHOD);
+    let mut prelude = vec![
+        ldc_i4_1(),
+        ldc_i4_1(),
+        call(Token::member_ref(0x0A00_0003).unwrap()).unwrap(),
+    ];
+    prelude.extend(vec![ldc_i4_1(); 3]);
+    prelude.extend(vec![pop(); 3]);
+    method.insert_prelude(prelude).unwrap();
+
+    // vararg void modopt(0x01000002) Log(int32, ..., int32)
+    let signature = |token| match token {
+        0x0A00_0003 => Some(vec![0x05, 0x02, 0x20, 0x09, 0x01, 0x08, 0x41, 0x08]),
+        _ => None,
+    };
+
+    assert_eq!(method.max_stack(signature), Ok(3));
+}
+
+#[test]
+fn given_catch_handler_when_computing_max_stack_then_exception_object_is_counted() {
+    let method = parse(&TRY_CATCH_METHOD);
+
+    assert_eq!(method.max_stack(|_| None), Ok(1));
+}
+
+#[test]
+fn given_unknown_call_signature_when_computing_max_stack_then_error_is_returned() {
+    let mut method = parse(&TINY_METHOD);
+    method
+        .insert_prelude(vec![call(Token::member_ref(0x0A00_0002).unwrap()).unwrap()])
+        .unwrap();
+
+    assert_eq!(
+        method.max_stack(|_| None),
+        Err(Error::UnresolvedSignature(0x0A00_0002))
+    );
+}
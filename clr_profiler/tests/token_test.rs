@@ -0,0 +1,59 @@
+mod fixtures;
+
+use clr_profiler::cil::{
+    assemble, call, ldstr, newarr, Error, Instruction, Method, Operand, Token, TokenTable, CALL,
+    NEWARR,
+};
+
+#[test]
+fn given_tokens_of_another_table_when_typing_them_then_they_are_rejected() {
+    let method = Token::method_def(0x0600_0001).unwrap();
+
+    assert_eq!(method.table(), TokenTable::MethodDef);
+    assert_eq!(method.rid(), 1);
+    assert_eq!(
+        Token::member_ref(0x0600_0001),
+        Err(Error::InvalidToken(0x0600_0001))
+    );
+    assert_eq!(
+        Token::from_raw(0x7F00_0001),
+        Err(Error::InvalidToken(0x7F00_0001))
+    );
+    assert_eq!(
+        Token::new(TokenTable::TypeSpec, 0x0100_0000),
+        Err(Error::InvalidToken(0x1B00_0000 | 0x0100_0000))
+    );
+    assert!(method.fits(&CALL.operand_params));
+    assert!(!method.fits(&NEWARR.operand_params));
+
+    // call 0x02000001, a type
+    assert_eq!(
+        Method::parse(&[0x1A, 0x28, 0x01, 0x00, 0x00, 0x02, 0x2A]),
+        Err(Error::Malformed(
+            1,
+            "invalid token 0x02000001 for `call`".to_string()
+        ))
+    );
+    assert!(assemble("call token(0x02000001)").is_err());
+}
+
+#[test]
+fn given_tokens_of_another_table_when_building_instructions_then_they_are_rejected() {
+    let type_def = Token::type_def(0x0200_0001).unwrap();
+    let string = Token::user_string(0x7000_0001).unwrap();
+
+    assert_eq!(call(type_def), Err(Error::InvalidToken(0x0200_0001)));
+    assert_eq!(ldstr(type_def), Err(Error::InvalidToken(0x0200_0001)));
+    assert_eq!(
+        Instruction::with_token(CALL, string),
+        Err(Error::InvalidToken(0x7000_0001))
+    );
+    assert_eq!(
+        newarr(type_def),
+        Ok(Instruction::new(NEWARR, Operand::InlineType(type_def)))
+    );
+    assert_eq!(
+        ldstr(string).map(|ldstr| ldstr.operand),
+        Ok(Operand::InlineString(string))
+    );
+}
@@ -0,0 +1,161 @@
+mod fixtures;
+
+use clr_profiler::cil::{
+    assemble_method, disassemble, verify, CallRedirect, Error, Token, Type, WrapHandler,
+};
+use clr_profiler::ffi::CorElementType;
+use clr_profiler::il;
+use fixtures::{parse, FakeMetadata, BRANCHING_METHOD};
+
+#[test]
+fn given_branching_method_when_wrapping_in_finally_then_rets_leave_through_the_handler() {
+    let metadata = FakeMetadata::with_locals(vec![0x07, 0x01, 0x0E]); // string
+    let mut method = parse(&BRANCHING_METHOD);
+    method.set_local_var_sig_tok(0x1100_0001);
+    let int32 = Type::Primitive(CorElementType::ELEMENT_TYPE_I4);
+
+    let return_local = method
+        .wrap(
+            WrapHandler::Finally,
+            il! { call 0x0A00_0001 },
+            &int32,
+            &metadata,
+            &metadata,
+        )
+        .unwrap();
+
+    assert_eq!(return_local, Some(1));
+    assert_eq!(
+        metadata.emitted.into_inner(),
+        vec![vec![0x07, 0x02, 0x0E, 0x08]]
+    );
+    let expected = "\
+.maxstack 1
+.locals init token(0x11000002)
+.try
+{
+  IL_0000: ldarg.0
+  IL_0001: brfalse.s IL_0007
+  IL_0003: ldc.i4.1
+  IL_0004: stloc.1
+  IL_0005: leave.s IL_0011
+  IL_0007: ldc.i4.0
+  IL_0008: stloc.1
+  IL_0009: leave.s IL_0011
+}
+finally
+{
+  IL_000b: call token(0x0A000001)
+  IL_0010: endfinally
+}
+IL_0011: ldloc.1
+IL_0012: ret
+";
+    assert_eq!(disassemble(&method, |_| None), expected);
+    assert_eq!(verify(&method, |_| Some(vec![0x00, 0x00, 0x01])), Ok(()));
+}
+
+#[test]
+fn given_branch_to_ret_when_inserting_epilogue_then_branch_runs_the_epilogue() {
+    let mut method = assemble_method(
+        "\
+  ldc.i4.0
+  ldarg.0
+  brfalse.s done
+  pop
+  ldc.i4.1
+  ret
+done:
+  ret",
+    )
+    .unwrap();
+
+    method
+        .insert_epilogue(il! { call 0x0A00_0002 }, true)
+        .unwrap();
+
+    let expected = "\
+.maxstack 8
+IL_0000: ldc.i4.0
+IL_0001: ldarg.0
+IL_0002: brfalse.s IL_000d
+IL_0004: pop
+IL_0005: ldc.i4.1
+IL_0006: dup
+IL_0007: call token(0x0A000002)
+IL_000c: ret
+IL_000d: dup
+IL_000e: call token(0x0A000002)
+IL_0013: ret
+";
+    assert_eq!(disassemble(&method, |_| None), expected);
+    assert_eq!(
+        verify(&method, |_| Some(vec![0x00, 0x01, 0x01, 0x08])),
+        Ok(())
+    );
+}
+
+#[test]
+fn given_virtual_call_sites_when_redirecting_to_static_wrapper_then_arguments_are_injected() {
+    let mut method = assemble_method(
+        "\
+  ldarg.0
+  ldarg.1
+  ldarg.2
+  brtrue.s site
+  pop
+  ldnull
+site:
+  callvirt token(0x0A000001)
+  pop
+  ldarg.0
+  ldarg.1
+  tail.
+  callvirt token(0x0A000001)
+  ret",
+    )
+    .unwrap();
+    let redirect = CallRedirect {
+        target: Token::member_ref(0x0A00_0001).unwrap(),
+        replacement: Token::method_def(0x0600_0002).unwrap(),
+        arguments: il! { ldc.i4.1 },
+        static_call: true,
+    };
+
+    assert_eq!(method.redirect_calls(&redirect), Ok(2));
+
+    let expected = "\
+.maxstack 8
+IL_0000: ldarg.0
+IL_0001: ldarg.1
+IL_0002: ldarg.2
+IL_0003: brtrue.s IL_0007
+IL_0005: pop
+IL_0006: ldnull
+IL_0007: ldc.i4.1
+IL_0008: call token(0x06000002)
+IL_000d: pop
+IL_000e: ldarg.0
+IL_000f: ldarg.1
+IL_0010: ldc.i4.1
+IL_0011: tail.
+IL_0013: call token(0x06000002)
+IL_0018: ret
+";
+    assert_eq!(disassemble(&method, |_| None), expected);
+    // object Send(object), static object Wrapper(object, object, int32)
+    let signature = |token| match token {
+        0x0A00_0001 => Some(vec![0x20, 0x01, 0x1C, 0x1C]),
+        _ => Some(vec![0x00, 0x03, 0x1C, 0x1C, 0x1C, 0x08]),
+    };
+    assert_eq!(verify(&method, signature), Ok(()));
+
+    let to_a_type = CallRedirect {
+        replacement: Token::type_def(0x0200_0001).unwrap(),
+        ..redirect
+    };
+    assert_eq!(
+        method.redirect_calls(&to_a_type),
+        Err(Error::InvalidToken(0x0200_0001))
+    );
+}
@@ -0,0 +1,79 @@
+mod fixtures;
+
+use clr_profiler::cil::{
+    ldarg_0, ldc_i4_1, pop, ret, verify, Diagnostic, Instruction, Label, Operand, BRTRUE_S, BR_S,
+};
+use fixtures::{parse, BRANCHING_METHOD, TINY_METHOD, TRY_CATCH_METHOD};
+
+#[test]
+fn given_parsed_try_catch_when_verifying_then_no_diagnostics_are_reported() {
+    let method = parse(&TRY_CATCH_METHOD);
+
+    assert_eq!(verify(&method, |_| None), Ok(()));
+}
+
+#[test]
+fn given_removed_ret_when_verifying_then_fall_off_end_is_reported() {
+    let mut method = parse(&BRANCHING_METHOD);
+    method.remove(5..6).unwrap();
+
+    assert_eq!(
+        verify(&method, |_| None),
+        Err(vec![Diagnostic::FallsOffEnd { index: 4 }])
+    );
+}
+
+#[test]
+fn given_merge_point_with_different_depths_when_verifying_then_mismatch_is_reported() {
+    let mut method = parse(&TINY_METHOD);
+    let end = Label(0x10);
+    method.instructions = vec![
+        ldarg_0(),
+        Instruction::new(BRTRUE_S, Operand::BrTarget(end)),
+        ldc_i4_1(),
+        ret().with_label(end),
+    ];
+
+    assert_eq!(
+        verify(&method, |_| None),
+        Err(vec![Diagnostic::StackMismatch {
+            index: 3,
+            expected: 0,
+            found: 1
+        }])
+    );
+}
+
+#[test]
+fn given_branch_out_of_try_block_when_verifying_then_missing_leave_is_reported() {
+    let mut method = parse(&TRY_CATCH_METHOD);
+    method.instructions[1].opcode = BR_S;
+
+    assert_eq!(
+        verify(&method, |_| None),
+        Err(vec![Diagnostic::ExitWithoutLeave { index: 1 }])
+    );
+}
+
+#[test]
+fn given_value_pushed_before_try_block_when_verifying_then_non_empty_stack_is_reported() {
+    let mut method = parse(&TRY_CATCH_METHOD);
+    method.insert_prelude(vec![ldc_i4_1()]).unwrap();
+
+    assert_eq!(
+        verify(&method, |_| None),
+        Err(vec![Diagnostic::NonEmptyStackOnEntry {
+            index: 1,
+            expected: 0,
+            found: 1
+        }])
+    );
+}
+
+#[test]
+fn given_value_left_on_stack_before_try_block_when_popped_then_no_diagnostics_are_reported() {
+    let mut method = parse(&TRY_CATCH_METHOD);
+    method.insert_prelude(vec![ldc_i4_1(), pop()]).unwrap();
+
+    assert_eq!(verify(&method, |_| None), Ok(()));
+}
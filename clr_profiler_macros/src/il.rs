@@ -0,0 +1,548 @@
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use std::collections::HashMap;
+use syn::{
+    braced,
+    ext::IdentExt,
+    parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Error, Ident, Lit, Result, Token,
+};
+use OperandKind::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OperandKind {
+    InlineNone,
+    ShortInlineVar,
+    InlineVar,
+    ShortInlineI,
+    InlineI,
+    InlineI8,
+    ShortInlineR,
+    InlineR,
+    InlineMethod,
+    InlineSig,
+    ShortInlineBrTarget,
+    InlineBrTarget,
+    InlineSwitch,
+    InlineType,
+    InlineString,
+    InlineField,
+    InlineTok,
+}
+
+/// Mnemonics of the opcodes in `clr_profiler::cil::opcode`, with the constant
+/// each one maps to and the kind of operand it takes. This crate can't depend
+/// on `clr_profiler`, so the table is kept in sync by hand and checked against
+/// it by the tests of `clr_profiler`, see `opcodes`.
+const OPCODES: &[(&str, &str, OperandKind)] = &[
+    ("nop", "NOP", InlineNone),
+    ("break", "BREAK", InlineNone),
+    ("ldarg.0", "LDARG_0", InlineNone),
+    ("ldarg.1", "LDARG_1", InlineNone),
+    ("ldarg.2", "LDARG_2", InlineNone),
+    ("ldarg.3", "LDARG_3", InlineNone),
+    ("ldloc.0", "LDLOC_0", InlineNone),
+    ("ldloc.1", "LDLOC_1", InlineNone),
+    ("ldloc.2", "LDLOC_2", InlineNone),
+    ("ldloc.3", "LDLOC_3", InlineNone),
+    ("stloc.0", "STLOC_0", InlineNone),
+    ("stloc.1", "STLOC_1", InlineNone),
+    ("stloc.2", "STLOC_2", InlineNone),
+    ("stloc.3", "STLOC_3", InlineNone),
+    ("ldarg.s", "LDARG_S", ShortInlineVar),
+    ("ldarga.s", "LDARGA_S", ShortInlineVar),
+    ("starg.s", "STARG_S", ShortInlineVar),
+    ("ldloc.s", "LDLOC_S", ShortInlineVar),
+    ("ldloca.s", "LDLOCA_S", ShortInlineVar),
+    ("stloc.s", "STLOC_S", ShortInlineVar),
+    ("ldnull", "LDNULL", InlineNone),
+    ("ldc.i4.m1", "LDC_I4_M1", InlineNone),
+    ("ldc.i4.0", "LDC_I4_0", InlineNone),
+    ("ldc.i4.1", "LDC_I4_1", InlineNone),
+    ("ldc.i4.2", "LDC_I4_2", InlineNone),
+    ("ldc.i4.3", "LDC_I4_3", InlineNone),
+    ("ldc.i4.4", "LDC_I4_4", InlineNone),
+    ("ldc.i4.5", "LDC_I4_5", InlineNone),
+    ("ldc.i4.6", "LDC_I4_6", InlineNone),
+    ("ldc.i4.7", "LDC_I4_7", InlineNone),
+    ("ldc.i4.8", "LDC_I4_8", InlineNone),
+    ("ldc.i4.s", "LDC_I4_S", ShortInlineI),
+    ("ldc.i4", "LDC_I4", InlineI),
+    ("ldc.i8", "LDC_I8", InlineI8),
+    ("ldc.r4", "LDC_R4", ShortInlineR),
+    ("ldc.r8", "LDC_R8", InlineR),
+    ("dup", "DUP", InlineNone),
+    ("pop", "POP", InlineNone),
+    ("jmp", "JMP", InlineMethod),
+    ("call", "CALL", InlineMethod),
+    ("calli", "CALLI", InlineSig),
+    ("ret", "RET", InlineNone),
+    ("br.s", "BR_S", ShortInlineBrTarget),
+    ("brfalse.s", "BRFALSE_S", ShortInlineBrTarget),
+    ("brtrue.s", "BRTRUE_S", ShortInlineBrTarget),
+    ("beq.s", "BEQ_S", ShortInlineBrTarget),
+    ("bge.s", "BGE_S", ShortInlineBrTarget),
+    ("bgt.s", "BGT_S", ShortInlineBrTarget),
+    ("ble.s", "BLE_S", ShortInlineBrTarget),
+    ("blt.s", "BLT_S", ShortInlineBrTarget),
+    ("bne.un.s", "BNE_UN_S", ShortInlineBrTarget),
+    ("bge.un.s", "BGE_UN_S", ShortInlineBrTarget),
+    ("bgt.un.s", "BGT_UN_S", ShortInlineBrTarget),
+    ("ble.un.s", "BLE_UN_S", ShortInlineBrTarget),
+    ("blt.un.s", "BLT_UN_S", ShortInlineBrTarget),
+    ("br", "BR", InlineBrTarget),
+    ("brfalse", "BRFALSE", InlineBrTarget),
+    ("brtrue", "BRTRUE", InlineBrTarget),
+    ("beq", "BEQ", InlineBrTarget),
+    ("bge", "BGE", InlineBrTarget),
+    ("bgt", "BGT", InlineBrTarget),
+    ("ble", "BLE", InlineBrTarget),
+    ("blt", "BLT", InlineBrTarget),
+    ("bne.un", "BNE_UN", InlineBrTarget),
+    ("bge.un", "BGE_UN", InlineBrTarget),
+    ("bgt.un", "BGT_UN", InlineBrTarget),
+    ("ble.un", "BLE_UN", InlineBrTarget),
+    ("blt.un", "BLT_UN", InlineBrTarget),
+    ("switch", "SWITCH", InlineSwitch),
+    ("ldind.i1", "LDIND_I1", InlineNone),
+    ("ldind.u1", "LDIND_U1", InlineNone),
+    ("ldind.i2", "LDIND_I2", InlineNone),
+    ("ldind.u2", "LDIND_U2", InlineNone),
+    ("ldind.i4", "LDIND_I4", InlineNone),
+    ("ldind.u4", "LDIND_U4", InlineNone),
+    ("ldind.i8", "LDIND_I8", InlineNone),
+    ("ldind.i", "LDIND_I", InlineNone),
+    ("ldind.r4", "LDIND_R4", InlineNone),
+    ("ldind.r8", "LDIND_R8", InlineNone),
+    ("ldind.ref", "LDIND_REF", InlineNone),
+    ("stind.ref", "STIND_REF", InlineNone),
+    ("stind.i1", "STIND_I1", InlineNone),
+    ("stind.i2", "STIND_I2", InlineNone),
+    ("stind.i4", "STIND_I4", InlineNone),
+    ("stind.i8", "STIND_I8", InlineNone),
+    ("stind.r4", "STIND_R4", InlineNone),
+    ("stind.r8", "STIND_R8", InlineNone),
+    ("add", "ADD", InlineNone),
+    ("sub", "SUB", InlineNone),
+    ("mul", "MUL", InlineNone),
+    ("div", "DIV", InlineNone),
+    ("div.un", "DIV_UN", InlineNone),
+    ("rem", "REM", InlineNone),
+    ("rem.un", "REM_UN", InlineNone),
+    ("and", "AND", InlineNone),
+    ("or", "OR", InlineNone),
+    ("xor", "XOR", InlineNone),
+    ("shl", "SHL", InlineNone),
+    ("shr", "SHR", InlineNone),
+    ("shr.un", "SHR_UN", InlineNone),
+    ("neg", "NEG", InlineNone),
+    ("not", "NOT", InlineNone),
+    ("conv.i1", "CONV_I1", InlineNone),
+    ("conv.i2", "CONV_I2", InlineNone),
+    ("conv.i4", "CONV_I4", InlineNone),
+    ("conv.i8", "CONV_I8", InlineNone),
+    ("conv.r4", "CONV_R4", InlineNone),
+    ("conv.r8", "CONV_R8", InlineNone),
+    ("conv.u4", "CONV_U4", InlineNone),
+    ("conv.u8", "CONV_U8", InlineNone),
+    ("callvirt", "CALLVIRT", InlineMethod),
+    ("cpobj", "CPOBJ", InlineType),
+    ("ldobj", "LDOBJ", InlineType),
+    ("ldstr", "LDSTR", InlineString),
+    ("newobj", "NEWOBJ", InlineMethod),
+    ("castclass", "CASTCLASS", InlineType),
+    ("isinst", "ISINST", InlineType),
+    ("conv.r.un", "CONV_R_UN", InlineNone),
+    ("unbox", "UNBOX", InlineType),
+    ("throw", "THROW", InlineNone),
+    ("ldfld", "LDFLD", InlineField),
+    ("ldflda", "LDFLDA", InlineField),
+    ("stfld", "STFLD", InlineField),
+    ("ldsfld", "LDSFLD", InlineField),
+    ("ldsflda", "LDSFLDA", InlineField),
+    ("stsfld", "STSFLD", InlineField),
+    ("stobj", "STOBJ", InlineType),
+    ("conv.ovf.i1.un", "CONV_OVF_I1_UN", InlineNone),
+    ("conv.ovf.i2.un", "CONV_OVF_I2_UN", InlineNone),
+    ("conv.ovf.i4.un", "CONV_OVF_I4_UN", InlineNone),
+    ("conv.ovf.i8.un", "CONV_OVF_I8_UN", InlineNone),
+    ("conv.ovf.u1.un", "CONV_OVF_U1_UN", InlineNone),
+    ("conv.ovf.u2.un", "CONV_OVF_U2_UN", InlineNone),
+    ("conv.ovf.u4.un", "CONV_OVF_U4_UN", InlineNone),
+    ("conv.ovf.u8.un", "CONV_OVF_U8_UN", InlineNone),
+    ("conv.ovf.i.un", "CONV_OVF_I_UN", InlineNone),
+    ("conv.ovf.u.un", "CONV_OVF_U_UN", InlineNone),
+    ("box", "BOX", InlineType),
+    ("newarr", "NEWARR", InlineType),
+    ("ldlen", "LDLEN", InlineNone),
+    ("ldelema", "LDELEMA", InlineType),
+    ("ldelem.i1", "LDELEM_I1", InlineNone),
+    ("ldelem.u1", "LDELEM_U1", InlineNone),
+    ("ldelem.i2", "LDELEM_I2", InlineNone),
+    ("ldelem.u2", "LDELEM_U2", InlineNone),
+    ("ldelem.i4", "LDELEM_I4", InlineNone),
+    ("ldelem.u4", "LDELEM_U4", InlineNone),
+    ("ldelem.i8", "LDELEM_I8", InlineNone),
+    ("ldelem.i", "LDELEM_I", InlineNone),
+    ("ldelem.r4", "LDELEM_R4", InlineNone),
+    ("ldelem.r8", "LDELEM_R8", InlineNone),
+    ("ldelem.ref", "LDELEM_REF", InlineNone),
+    ("stelem.i", "STELEM_I", InlineNone),
+    ("stelem.i1", "STELEM_I1", InlineNone),
+    ("stelem.i2", "STELEM_I2", InlineNone),
+    ("stelem.i4", "STELEM_I4", InlineNone),
+    ("stelem.i8", "STELEM_I8", InlineNone),
+    ("stelem.r4", "STELEM_R4", InlineNone),
+    ("stelem.r8", "STELEM_R8", InlineNone),
+    ("stelem.ref", "STELEM_REF", InlineNone),
+    ("ldelem", "LDELEM", InlineType),
+    ("stelem", "STELEM", InlineType),
+    ("unbox.any", "UNBOX_ANY", InlineType),
+    ("conv.ovf.i1", "CONV_OVF_I1", InlineNone),
+    ("conv.ovf.u1", "CONV_OVF_U1", InlineNone),
+    ("conv.ovf.i2", "CONV_OVF_I2", InlineNone),
+    ("conv.ovf.u2", "CONV_OVF_U2", InlineNone),
+    ("conv.ovf.i4", "CONV_OVF_I4", InlineNone),
+    ("conv.ovf.u4", "CONV_OVF_U4", InlineNone),
+    ("conv.ovf.i8", "CONV_OVF_I8", InlineNone),
+    ("conv.ovf.u8", "CONV_OVF_U8", InlineNone),
+    ("refanyval", "REFANYVAL", InlineType),
+    ("ckfinite", "CKFINITE", InlineNone),
+    ("mkrefany", "MKREFANY", InlineType),
+    ("ldtoken", "LDTOKEN", InlineTok),
+    ("conv.u2", "CONV_U2", InlineNone),
+    ("conv.u1", "CONV_U1", InlineNone),
+    ("conv.i", "CONV_I", InlineNone),
+    ("conv.ovf.i", "CONV_OVF_I", InlineNone),
+    ("conv.ovf.u", "CONV_OVF_U", InlineNone),
+    ("add.ovf", "ADD_OVF", InlineNone),
+    ("add.ovf.un", "ADD_OVF_UN", InlineNone),
+    ("mul.ovf", "MUL_OVF", InlineNone),
+    ("mul.ovf.un", "MUL_OVF_UN", InlineNone),
+    ("sub.ovf", "SUB_OVF", InlineNone),
+    ("sub.ovf.un", "SUB_OVF_UN", InlineNone),
+    ("endfinally", "ENDFINALLY", InlineNone),
+    ("leave", "LEAVE", InlineBrTarget),
+    ("leave.s", "LEAVE_S", ShortInlineBrTarget),
+    ("stind.i", "STIND_I", InlineNone),
+    ("conv.u", "CONV_U", InlineNone),
+    ("arglist", "ARGLIST", InlineNone),
+    ("ceq", "CEQ", InlineNone),
+    ("cgt", "CGT", InlineNone),
+    ("cgt.un", "CGT_UN", InlineNone),
+    ("clt", "CLT", InlineNone),
+    ("clt.un", "CLT_UN", InlineNone),
+    ("ldftn", "LDFTN", InlineMethod),
+    ("ldvirtftn", "LDVIRTFTN", InlineMethod),
+    ("ldarg", "LDARG", InlineVar),
+    ("ldarga", "LDARGA", InlineVar),
+    ("starg", "STARG", InlineVar),
+    ("ldloc", "LDLOC", InlineVar),
+    ("ldloca", "LDLOCA", InlineVar),
+    ("stloc", "STLOC", InlineVar),
+    ("localloc", "LOCALLOC", InlineNone),
+    ("endfilter", "ENDFILTER", InlineNone),
+    ("unaligned.", "UNALIGNED", ShortInlineI),
+    ("volatile.", "VOLATILE", InlineNone),
+    ("tail.", "TAILCALL", InlineNone),
+    ("initobj", "INITOBJ", InlineType),
+    ("constrained.", "CONSTRAINED", InlineType),
+    ("cpblk", "CPBLK", InlineNone),
+    ("initblk", "INITBLK", InlineNone),
+    ("rethrow", "RETHROW", InlineNone),
+    ("sizeof", "SIZEOF", InlineType),
+    ("refanytype", "REFANYTYPE", InlineNone),
+    ("readonly.", "READONLY", InlineNone),
+];
+
+/// The body of an `il!` invocation.
+pub struct Il {
+    statements: Vec<Statement>,
+}
+struct Statement {
+    labels: Vec<Ident>,
+    mnemonic: String,
+    span: Span,
+    operand: Option<OperandSyntax>,
+}
+enum OperandSyntax {
+    /// A literal, possibly negated.
+    Literal(Lit, bool),
+    /// A braced Rust expression.
+    Expr(TokenStream),
+    Label(Ident),
+    Labels(Vec<Ident>),
+}
+
+impl Parse for Il {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut statements = Vec::new();
+        let mut labels = Vec::new();
+        while !input.is_empty() {
+            let ident = Ident::parse_any(input)?;
+            if input.peek(Token![:]) && !input.peek(Token![::]) {
+                input.parse::<Token![:]>()?;
+                labels.push(ident);
+                continue;
+            }
+            let span = ident.span();
+            let mut mnemonic = ident.unraw().to_string();
+            while input.peek(Token![.]) {
+                input.parse::<Token![.]>()?;
+                mnemonic.push('.');
+                // Prefixes such as `tail.` end in a dot and precede another
+                // mnemonic, so stop before swallowing it.
+                if OPCODES.iter().any(|(name, _, _)| *name == mnemonic) {
+                    break;
+                }
+                if input.peek(syn::LitInt) {
+                    mnemonic.push_str(&input.parse::<syn::LitInt>()?.to_string());
+                } else if input.peek(Ident::peek_any) {
+                    mnemonic.push_str(&Ident::parse_any(input)?.unraw().to_string());
+                }
+            }
+            let prefix = OPCODES
+                .iter()
+                .find(|(name, _, _)| name.ends_with('.') && *name == mnemonic);
+            let operand = if input.is_empty()
+                || input.peek(Token![;])
+                || matches!(prefix, Some((_, _, OperandKind::InlineNone)))
+            {
+                None
+            } else {
+                Some(input.parse()?)
+            };
+            if prefix.is_some() {
+                input.parse::<Option<Token![;]>>()?;
+            } else if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+            statements.push(Statement {
+                labels: std::mem::take(&mut labels),
+                mnemonic,
+                span,
+                operand,
+            });
+        }
+        if let Some(label) = labels.first() {
+            return Err(Error::new(label.span(), "label marks no instruction"));
+        }
+        Ok(Il { statements })
+    }
+}
+impl Parse for OperandSyntax {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+            Ok(OperandSyntax::Expr(content.parse()?))
+        } else if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let labels = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+            Ok(OperandSyntax::Labels(labels.into_iter().collect()))
+        } else if input.peek(Ident) {
+            Ok(OperandSyntax::Label(input.parse()?))
+        } else {
+            let negative = input.parse::<Option<Token![-]>>()?.is_some();
+            Ok(OperandSyntax::Literal(input.parse()?, negative))
+        }
+    }
+}
+
+impl Il {
+    pub fn expand(&self) -> Result<TokenStream> {
+        // Labels of the same statement name the same instruction, so share a number
+        let mut labels = HashMap::new();
+        let labelled = self.statements.iter().filter(|s| !s.labels.is_empty());
+        for (next, statement) in labelled.enumerate() {
+            for label in statement.labels.iter() {
+                if labels.insert(label.to_string(), next as u32).is_some() {
+                    return Err(Error::new(label.span(), "label is defined twice"));
+                }
+            }
+        }
+        let label = |ident: &Ident| match labels.get(&ident.to_string()) {
+            Some(label) => Ok(quote! { clr_profiler::cil::Label(#label) }),
+            None => Err(Error::new(ident.span(), "undefined label")),
+        };
+        let mut instructions = Vec::new();
+        for statement in self.statements.iter() {
+            let (constant, kind) = OPCODES
+                .iter()
+                .find(|(name, _, _)| *name == statement.mnemonic)
+                .map(|(_, constant, kind)| (format_ident!("{}", constant), *kind))
+                .ok_or_else(|| {
+                    let message = format!("unknown mnemonic `{}`", statement.mnemonic);
+                    Error::new(statement.span, message)
+                })?;
+            let operand = operand(statement, kind, &label)?;
+            let mut instruction = quote_spanned! {statement.span=>
+                clr_profiler::cil::Instruction::new(
+                    clr_profiler::cil::#constant,
+                    clr_profiler::cil::Operand::#operand,
+                )
+            };
+            if let Some(first) = statement.labels.first() {
+                let label = label(first)?;
+                instruction = quote! { #instruction.with_label(#label) };
+            }
+            instructions.push(instruction);
+        }
+        Ok(quote! { vec![#(#instructions),*] })
+    }
+}
+
+/// The table of mnemonics as a slice of tuples of the mnemonic, the opcode
+/// constant and its operand params.
+pub fn opcodes() -> TokenStream {
+    let entries = OPCODES.iter().map(|(name, constant, kind)| {
+        let constant = format_ident!("{}", constant);
+        let kind = Ident::new(&format!("{:?}", kind), Span::call_site());
+        quote! {
+            (
+                #name,
+                clr_profiler::cil::#constant,
+                clr_profiler::cil::OperandParams::#kind,
+            )
+        }
+    });
+    quote! {
+        &[#(#entries),*]
+    }
+}
+
+fn operand<F>(statement: &Statement, kind: OperandKind, label: &F) -> Result<TokenStream>
+where
+    F: Fn(&Ident) -> Result<TokenStream>,
+{
+    let span = statement.span;
+    let expected = |what: &str| {
+        let message = format!("`{}` expects {}", statement.mnemonic, what);
+        Err(Error::new(span, message))
+    };
+    // Values beyond the signed range wrap, e.g. `ldc.i4 0xFFFFFFFF` is -1
+    let integer = |min: i128, max: i128, typed: fn(i128) -> Literal| match &statement.operand {
+        Some(OperandSyntax::Literal(Lit::Int(literal), negative)) => {
+            let value = literal.base10_parse::<i128>()?;
+            let value = if *negative { -value } else { value };
+            if value < min || value > max {
+                return Err(Error::new(literal.span(), "integer out of range"));
+            }
+            let value = typed(value);
+            Ok(quote! { #value })
+        }
+        Some(OperandSyntax::Expr(expr)) => Ok(quote! { { #expr } }),
+        _ => expected("an integer"),
+    };
+    let float = |typed: fn(f64) -> Literal| match &statement.operand {
+        Some(OperandSyntax::Literal(Lit::Float(literal), negative)) => {
+            let value = literal.base10_parse::<f64>()?;
+            let value = typed(if *negative { -value } else { value });
+            Ok(quote! { #value })
+        }
+        Some(OperandSyntax::Literal(Lit::Int(literal), negative)) => {
+            let value = literal.base10_parse::<f64>()?;
+            let value = typed(if *negative { -value } else { value });
+            Ok(quote! { #value })
+        }
+        Some(OperandSyntax::Expr(expr)) => Ok(quote! { { #expr } }),
+        _ => expected("a floating point number"),
+    };
+    let token = || match &statement.operand {
+        Some(OperandSyntax::Literal(Lit::Int(literal), false)) => {
+            let value = literal.base10_parse::<u32>()?;
+            Ok(quote! { #value })
+        }
+        Some(OperandSyntax::Expr(expr)) => Ok(quote! { { #expr } }),
+        _ => expected("a metadata token"),
+    };
+    // Literal tokens are checked against the tables the operand may refer to
+    // here, so the `Token` built from them can't fail at run time.
+    let typed_token = |tables: &[u8]| match &statement.operand {
+        Some(OperandSyntax::Literal(Lit::Int(literal), false)) => {
+            let value = literal.base10_parse::<u32>()?;
+            if !tables.contains(&((value >> 24) as u8)) {
+                let message = format!("`{}` can't refer to this token", statement.mnemonic);
+                return Err(Error::new(literal.span(), message));
+            }
+            Ok(quote! { clr_profiler::cil::Token::from_raw(#value).unwrap() })
+        }
+        _ => token(),
+    };
+    let variant = |name: &str| format_ident!("{}", name);
+    let operand = match kind {
+        InlineNone => match &statement.operand {
+            None => quote! { InlineNone },
+            Some(_) => return expected("no operand"),
+        },
+        ShortInlineVar => {
+            let value = integer(0, u8::MAX as i128, |v| Literal::u8_suffixed(v as u8))?;
+            quote! { ShortInlineVar(#value) }
+        }
+        InlineVar => {
+            let value = integer(0, u16::MAX as i128, |v| Literal::u16_suffixed(v as u16))?;
+            quote! { InlineVar(#value) }
+        }
+        // `ldc.i4.s` and `unaligned.` take a signed and an unsigned byte
+        ShortInlineI => {
+            let value = integer(i8::MIN as i128, u8::MAX as i128, |v| {
+                Literal::u8_suffixed(v as u8)
+            })?;
+            quote! { ShortInlineI(#value) }
+        }
+        InlineI => {
+            let value = integer(i32::MIN as i128, u32::MAX as i128, |v| {
+                Literal::i32_suffixed(v as i32)
+            })?;
+            quote! { InlineI(#value) }
+        }
+        InlineI8 => {
+            let value = integer(i64::MIN as i128, u64::MAX as i128, |v| {
+                Literal::i64_suffixed(v as i64)
+            })?;
+            quote! { InlineI8(#value) }
+        }
+        ShortInlineR => {
+            let value = float(|v| Literal::f32_suffixed(v as f32))?;
+            quote! { ShortInlineR(#value) }
+        }
+        InlineR => {
+            let value = float(Literal::f64_suffixed)?;
+            quote! { InlineR(#value) }
+        }
+        InlineSig => {
+            let value = token()?;
+            quote! { InlineSig(#value) }
+        }
+        InlineMethod | InlineType | InlineString | InlineField | InlineTok => {
+            let name = variant(&format!("{:?}", kind));
+            let tables: &[u8] = match kind {
+                InlineMethod => &[0x06, 0x0A, 0x2B],
+                InlineType => &[0x01, 0x02, 0x1B],
+                InlineString => &[0x70],
+                InlineField => &[0x04, 0x0A],
+                _ => &[0x01, 0x02, 0x1B, 0x06, 0x0A, 0x2B, 0x04],
+            };
+            let value = typed_token(tables)?;
+            quote! { #name(#value) }
+        }
+        ShortInlineBrTarget | InlineBrTarget => match &statement.operand {
+            Some(OperandSyntax::Label(ident)) => {
+                let target = label(ident)?;
+                quote! { BrTarget(#target) }
+            }
+            _ => return expected("a label"),
+        },
+        InlineSwitch => match &statement.operand {
+            Some(OperandSyntax::Labels(idents)) => {
+                let targets = idents.iter().map(label).collect::<Result<Vec<_>>>()?;
+                quote! { SwitchTargets(vec![#(#targets),*]) }
+            }
+            _ => return expected("a parenthesized list of labels"),
+        },
+    };
+    Ok(operand)
+}
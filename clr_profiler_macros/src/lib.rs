@@ -1,8 +1,43 @@
 extern crate proc_macro;
+mod il;
+
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput, Type};
 
+/// Assembles IL into a `Vec<clr_profiler::cil::Instruction>` at compile time:
+/// ```ignore
+/// let prelude = il! {
+///         ldarg.0;
+///         brfalse.s skip;
+///         ldc.i4.s -2;
+///         ldc.i4 { counter_id };
+///         call { probe_token };
+///     skip:
+///         nop;
+/// };
+/// ```
+/// Mnemonics are the ones of `Opcode::name`. Branches and `switch (a, b)`
+/// refer to labels, which are local to the snippet, see `Method::insert`.
+/// Braced Rust expressions can stand in for any literal or token operand.
+#[proc_macro]
+pub fn il(item: TokenStream) -> TokenStream {
+    let il = parse_macro_input!(item as il::Il);
+    match il.expand() {
+        Ok(output) => output.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Expands the mnemonic table of `il!` to a
+/// `&[(&str, clr_profiler::cil::Opcode, clr_profiler::cil::OperandParams)]`,
+/// so tests can check it against `clr_profiler::cil::opcode`.
+#[doc(hidden)]
+#[proc_macro]
+pub fn il_opcodes(_item: TokenStream) -> TokenStream {
+    il::opcodes().into()
+}
+
 #[proc_macro]
 pub fn register(item: TokenStream) -> TokenStream {
     let profiler_type = parse_macro_input!(item as Type);
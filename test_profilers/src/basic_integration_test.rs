@@ -1,11 +1,11 @@
 use clr_profiler::{
-    cil::{nop, Method},
+    cil::{method_name, NameStyle},
     ffi::{CorOpenFlags, FunctionID, COR_PRF_MONITOR, E_FAIL, HRESULT},
-    register, ClrProfiler, CorProfilerCallback, CorProfilerCallback2, CorProfilerCallback3,
+    register, ClassNameResolver, ClrProfiler, CorProfilerCallback, CorProfilerCallback2, CorProfilerCallback3,
     CorProfilerCallback4, CorProfilerCallback5, CorProfilerCallback6, CorProfilerCallback7,
-    CorProfilerCallback8, CorProfilerCallback9, CorProfilerInfo, MetadataImportTrait, ProfilerInfo, CorProfilerInfo2, CorProfilerInfo4,
+    CorProfilerCallback8, CorProfilerCallback9, CorProfilerInfo, ProfilerInfo, CorProfilerInfo2, CorProfilerInfo4,
 };
-use std::{slice, sync::mpsc::{Sender, SendError}, error::Error};
+use std::sync::mpsc::Sender;
 use std::process;
 use uuid::Uuid;
 use std::sync::mpsc;
@@ -25,7 +25,9 @@ struct Profiler {
     tx: Option<Sender<ClientRequests>>,
     ctrl: Option<Sender<ControlRequests>>,
     client: Option<std::rc::Rc<std::thread::JoinHandle<()>>>,
-    object_ids: std::collections::HashSet<clr_profiler::ffi::ObjectID>
+    object_ids: std::collections::HashSet<clr_profiler::ffi::ObjectID>,
+    class_names: Option<ClassNameResolver<ProfilerInfo>>,
+    ilasm_class_names: Option<ClassNameResolver<ProfilerInfo>>
 }
 impl Profiler {
     fn profiler_info(&self) -> &ProfilerInfo {
@@ -41,22 +43,32 @@ impl Profiler {
         }
     }
 
-    fn get_class_name(&self, class_id: clr_profiler::ffi::ClassID) -> Result<String, FFI_HRESULT> {
-        let class_info = self.profiler_info().get_class_id_info(class_id)?;
-        let module_metadata = self
-            .profiler_info()
-            .get_module_metadata(class_info.module_id, CorOpenFlags::ofRead)?;
-        let class_props = module_metadata.get_typedef_props(class_info.token)?;
-        Ok(class_props.name)
+    fn get_class_name(&mut self, class_id: clr_profiler::ffi::ClassID) -> Result<String, FFI_HRESULT> {
+        self.class_names.as_mut().unwrap().name(class_id)
     }
 
-    fn get_method_name(&self, function_id: FunctionID) -> Result<String, FFI_HRESULT> {
-        let function_info = self.profiler_info().get_function_info(function_id)?;
+    fn get_method_name(&mut self, function_id: FunctionID) -> Result<String, FFI_HRESULT> {
+        let function_info = self.profiler_info().get_function_info_2(function_id, 0)?;
         let module_metadata = self
             .profiler_info()
             .get_module_metadata(function_info.module_id, CorOpenFlags::ofRead)?;
-        let method_props = module_metadata.get_method_props(function_info.token)?;
-        Ok(method_props.name)
+        // Type arguments are named in the ILAsm style of the rest of the method name
+        let class_type_args = match function_info.class_id {
+            0 => Vec::new(),
+            class_id => self.profiler_info().get_class_id_info_2(class_id)?.type_args,
+        };
+        let ilasm_class_names = self.ilasm_class_names.as_mut().unwrap();
+        let class_type_args = class_type_args
+            .iter()
+            .map(|class_id| ilasm_class_names.name(*class_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let method_type_args = function_info
+            .type_args
+            .iter()
+            .map(|class_id| ilasm_class_names.name(*class_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        method_name(&module_metadata, function_info.token, &class_type_args, &method_type_args)
+            .or(Err(E_FAIL))
     }
 }
 impl ClrProfiler for Profiler {
@@ -67,7 +79,9 @@ impl ClrProfiler for Profiler {
             tx: None,
             ctrl: None,
             client: None,
-            object_ids: std::collections::HashSet::new()
+            object_ids: std::collections::HashSet::new(),
+            class_names: None,
+            ilasm_class_names: None
         }
     }
     fn clsid(&self) -> &Uuid {
@@ -77,6 +91,9 @@ impl ClrProfiler for Profiler {
 impl CorProfilerCallback for Profiler {
     fn initialize(&mut self, profiler_info: ProfilerInfo) -> Result<(), HRESULT> {
         // Initialize ICorProfilerInfo reference
+        self.class_names = Some(ClassNameResolver::new(profiler_info.clone()));
+        self.ilasm_class_names =
+            Some(ClassNameResolver::with_style(profiler_info.clone(), NameStyle::ILAsm));
         self.profiler_info = Some(profiler_info);
 
         // Set the event mask
@@ -203,6 +220,8 @@ impl CorProfilerCallback for Profiler {
             Ok(name) => name,
             Err(_) => "Unknown".to_string()
         };
+        self.class_names.as_mut().unwrap().class_unload_finished(class_id);
+        self.ilasm_class_names.as_mut().unwrap().class_unload_finished(class_id);
         Profiler::send_request(&self.tx,
             ClientRequests::ClassUnloadFinishStamp(get_time(), class_name))
     }